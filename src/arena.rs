@@ -0,0 +1,144 @@
+use bevy::prelude::*;
+
+/// Side of a square pillar
+pub const PILLAR_SIZE: f32 = 30.;
+/// Thickness of the arms of an L-shaped obstacle
+pub const OBSTACLE_THICKNESS: f32 = 20.;
+/// Hits the cover in the default layout takes before it breaks
+pub const COVER_HIT_POINTS: u32 = 5;
+
+/// Piece of cover inside the arena
+#[derive(Clone, Debug, PartialEq)]
+pub enum Obstacle {
+    Block {
+        center: Vec2,
+        size: Vec2,
+    },
+    /// Square of `PILLAR_SIZE`
+    Pillar {
+        center: Vec2,
+    },
+    /// Two arms joined at `corner`, running `arms.x` along the x axis and
+    /// `arms.y` along the y axis (negative lengths point left or down)
+    LShape {
+        corner: Vec2,
+        arms: Vec2,
+    },
+    /// Block absorbing bullets, which breaks after `hit_points` hits
+    Cover {
+        center: Vec2,
+        size: Vec2,
+        hit_points: u32,
+    },
+}
+
+impl Obstacle {
+    /// Boxes the obstacle is made of, as centers and sizes
+    pub fn boxes(&self) -> Vec<(Vec2, Vec2)> {
+        match *self {
+            Obstacle::Block { center, size } | Obstacle::Cover { center, size, .. } => {
+                vec![(center, size)]
+            }
+            Obstacle::Pillar { center } => vec![(center, Vec2::splat(PILLAR_SIZE))],
+            Obstacle::LShape { corner, arms } => {
+                // The horizontal arm covers the corner itself, and both arms
+                // end `arms` away from the corner's center
+                let outward = arms.signum() * OBSTACLE_THICKNESS / 2.;
+                vec![
+                    (
+                        corner + Vec2::new((arms.x - outward.x) / 2., 0.),
+                        Vec2::new(arms.x.abs() + OBSTACLE_THICKNESS / 2., OBSTACLE_THICKNESS),
+                    ),
+                    (
+                        corner + Vec2::new(0., (arms.y + outward.y) / 2.),
+                        Vec2::new(OBSTACLE_THICKNESS, arms.y.abs() - OBSTACLE_THICKNESS / 2.),
+                    ),
+                ]
+            }
+        }
+    }
+}
+
+/// Obstacles placed inside the arena when the game starts.
+///
+/// They should keep clear of the player's starting spot and of the edges
+/// enemies come in from. The default layout does, for both the desktop and
+/// the mobile arena.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct ArenaLayout {
+    pub obstacles: Vec<Obstacle>,
+}
+
+impl ArenaLayout {
+    /// Just the walls around the arena
+    pub fn empty() -> Self {
+        Self {
+            obstacles: Vec::new(),
+        }
+    }
+}
+
+impl Default for ArenaLayout {
+    fn default() -> Self {
+        let mut obstacles = Vec::new();
+        // Mirrored on both sides, leaving the middle of the arena open
+        for side in [-1., 1.] {
+            obstacles.push(Obstacle::Pillar {
+                center: Vec2::new(side * 90., -60.),
+            });
+            obstacles.push(Obstacle::Block {
+                center: Vec2::new(side * 200., 120.),
+                size: Vec2::new(80., 20.),
+            });
+            // Open towards the bottom, where enemies coming from above
+            // can't get stuck inside
+            obstacles.push(Obstacle::LShape {
+                corner: Vec2::new(side * 260., 30.),
+                arms: Vec2::new(-side * 70., -70.),
+            });
+        }
+        // A row of cover shielding the player, in the middle of which
+        // bullets can fly straight up
+        for x in [-180., -60., 60., 180.] {
+            obstacles.push(Obstacle::Cover {
+                center: Vec2::new(x, -170.),
+                size: Vec2::new(50., 14.),
+                hit_points: COVER_HIT_POINTS,
+            });
+        }
+        Self { obstacles }
+    }
+}
+
+/// Obstacle that bullets wear down, breaking when out of hit points
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Cover {
+    pub hit_points: u32,
+    pub max_hit_points: u32,
+}
+
+impl Cover {
+    pub fn new(hit_points: u32) -> Self {
+        Self {
+            hit_points,
+            max_hit_points: hit_points,
+        }
+    }
+
+    /// Takes a hit, returning whether it broke the cover
+    pub(crate) fn hit(&mut self) -> bool {
+        if self.hit_points == 0 {
+            return false;
+        }
+        self.hit_points -= 1;
+        self.hit_points == 0
+    }
+}
+
+/// Fades cover out as it takes hits
+pub fn fade_damaged_cover(mut query: Query<(&Cover, &mut Sprite), Changed<Cover>>) {
+    for (cover, mut sprite) in query.iter_mut() {
+        let health = cover.hit_points as f32 / cover.max_hit_points.max(1) as f32;
+        sprite.color.set_a(0.25 + 0.75 * health);
+    }
+}
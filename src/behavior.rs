@@ -0,0 +1,331 @@
+use std::f32::consts::TAU;
+
+use bevy::{prelude::*, utils::HashMap};
+use rand::Rng as _;
+use rand_pcg::Pcg32;
+
+use crate::{EnemyTable, EnemyTableError, WaveScript, WaveScriptError};
+
+/// What an enemy knows about its surroundings when deciding where to go
+#[derive(Clone, Debug)]
+pub struct Steering {
+    pub position: Vec2,
+    pub player: Vec2,
+    /// Units per second the player is moving at, and which way
+    pub player_velocity: Vec2,
+    /// Unit vector towards the player, around the walls when they are in the way
+    pub path: Vec2,
+    /// Whether nothing stands between the enemy and the player
+    pub in_sight: bool,
+    /// Units per second the enemy moves at, at full pace
+    pub speed: f32,
+    /// Fraction of its health the enemy has left
+    pub health: f32,
+    /// Seconds the tick lasts
+    pub delta: f32,
+}
+
+/// What a behavior remembers about an enemy between ticks, free for each
+/// behavior to use as it needs
+#[derive(Component, Clone, Debug, Default, PartialEq)]
+pub struct BehaviorState {
+    pub timer: f32,
+    pub heading: Vec2,
+    /// Which step of a repeating pattern the enemy is on
+    pub phase: u32,
+}
+
+/// A way for enemies to move.
+///
+/// Behaviors are shared by every enemy of the kinds using them, so anything
+/// kept from one tick to the next goes in the enemy's [`BehaviorState`].
+/// Randomness must come from `rng` for games to stay reproducible.
+pub trait Behavior: Send + Sync {
+    /// Heading for this tick, as a multiple of the enemy's full speed: a unit
+    /// vector moves at full pace and a zero one stands still
+    fn steer(&self, steering: &Steering, state: &mut BehaviorState, rng: &mut Pcg32) -> Vec2;
+
+    /// What is wrong with the way the behavior is set up, if anything.
+    /// Enemy tables using a behavior with a problem are rejected.
+    fn problem(&self) -> Option<String> {
+        None
+    }
+}
+
+/// Heads straight for the player, or around the walls in the way
+pub struct Chase;
+
+impl Behavior for Chase {
+    fn steer(&self, steering: &Steering, _state: &mut BehaviorState, _rng: &mut Pcg32) -> Vec2 {
+        steering.path
+    }
+}
+
+/// Chases the player, weaving from side to side
+pub struct Zigzag {
+    /// Widest angle off the path, in radians
+    pub amplitude: f32,
+    /// Seconds to weave to one side and back
+    pub period: f32,
+}
+
+impl Default for Zigzag {
+    fn default() -> Self {
+        Self {
+            amplitude: 0.8,
+            period: 1.2,
+        }
+    }
+}
+
+impl Behavior for Zigzag {
+    fn steer(&self, steering: &Steering, state: &mut BehaviorState, _rng: &mut Pcg32) -> Vec2 {
+        state.timer = (state.timer + steering.delta) % self.period;
+        let angle = self.amplitude * (TAU * state.timer / self.period).sin();
+        Vec2::from_angle(angle).rotate(steering.path)
+    }
+
+    fn problem(&self) -> Option<String> {
+        (!self.period.is_finite() || self.period <= 0.)
+            .then(|| format!("weaves with a period of {} seconds", self.period))
+    }
+}
+
+/// Circles the player at a distance, once it has a clear view of it
+pub struct Orbit {
+    pub radius: f32,
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self { radius: 160. }
+    }
+}
+
+impl Behavior for Orbit {
+    fn steer(&self, steering: &Steering, _state: &mut BehaviorState, _rng: &mut Pcg32) -> Vec2 {
+        let offset = steering.position - steering.player;
+        let distance = offset.length();
+        if !steering.in_sight || distance == 0. {
+            return steering.path;
+        }
+        let outward = offset / distance;
+        // Counterclockwise around the player, drifting back to the radius
+        let drift = ((self.radius - distance) / self.radius).clamp(-1., 1.);
+        (outward.perp() + outward * drift * 2.).normalize_or_zero()
+    }
+}
+
+/// Charges at the player much faster than it walks, then stops to catch its
+/// breath
+pub struct Dash {
+    /// Seconds a charge lasts
+    pub charge: f32,
+    /// Seconds of standing still between charges
+    pub pause: f32,
+    /// Multiple of the enemy's speed it charges at
+    pub pace: f32,
+}
+
+impl Default for Dash {
+    fn default() -> Self {
+        Self {
+            charge: 0.5,
+            pause: 0.8,
+            pace: 3.,
+        }
+    }
+}
+
+impl Behavior for Dash {
+    fn steer(&self, steering: &Steering, state: &mut BehaviorState, _rng: &mut Pcg32) -> Vec2 {
+        state.timer -= steering.delta;
+        if state.timer <= 0. {
+            // Phase 1 is a charge, keeping the heading it started with for
+            // the whole of it, and phase 0 a pause
+            if state.phase == 0 {
+                state.phase = 1;
+                state.heading = steering.path;
+                state.timer += self.charge;
+            } else {
+                state.phase = 0;
+                state.heading = Vec2::ZERO;
+                state.timer += self.pause;
+            }
+        }
+        state.heading * self.pace
+    }
+}
+
+/// Roams about aimlessly, only slowly drawn towards the player
+pub struct Wander {
+    /// Most the heading turns in a second, in radians
+    pub turn: f32,
+    /// How strongly the player draws it in, compared to roaming
+    pub pull: f32,
+    /// Multiple of the enemy's speed it roams at
+    pub pace: f32,
+}
+
+impl Default for Wander {
+    fn default() -> Self {
+        Self {
+            turn: 4.,
+            pull: 0.3,
+            pace: 0.7,
+        }
+    }
+}
+
+impl Behavior for Wander {
+    fn steer(&self, steering: &Steering, state: &mut BehaviorState, rng: &mut Pcg32) -> Vec2 {
+        if state.heading == Vec2::ZERO {
+            state.heading = Vec2::from_angle(rng.gen::<f32>() * TAU);
+        }
+        let turn = (rng.gen::<f32>() * 2. - 1.) * self.turn * steering.delta;
+        let heading = Vec2::from_angle(turn).rotate(state.heading);
+        state.heading = (heading + steering.path * self.pull * steering.delta)
+            .try_normalize()
+            .unwrap_or(heading);
+        state.heading * self.pace
+    }
+}
+
+/// Chases the player until badly hurt, then runs away from it
+pub struct Flee {
+    /// Fraction of its health left below which the enemy runs
+    pub below: f32,
+}
+
+impl Default for Flee {
+    fn default() -> Self {
+        Self { below: 0.5 }
+    }
+}
+
+impl Behavior for Flee {
+    fn steer(&self, steering: &Steering, _state: &mut BehaviorState, _rng: &mut Pcg32) -> Vec2 {
+        if steering.health > self.below {
+            return steering.path;
+        }
+        (steering.position - steering.player).normalize_or_zero()
+    }
+}
+
+/// Heads for where the player will be when it gets there, rather than where
+/// the player is
+pub struct Intercept {
+    /// Most seconds ahead it guesses the player's position
+    pub lead: f32,
+}
+
+impl Default for Intercept {
+    fn default() -> Self {
+        Self { lead: 1.5 }
+    }
+}
+
+impl Behavior for Intercept {
+    fn steer(&self, steering: &Steering, _state: &mut BehaviorState, _rng: &mut Pcg32) -> Vec2 {
+        if !steering.in_sight || steering.speed <= 0. {
+            return steering.path;
+        }
+        let time = (steering.position.distance(steering.player) / steering.speed).min(self.lead);
+        let target = steering.player + steering.player_velocity * time;
+        (target - steering.position)
+            .try_normalize()
+            .unwrap_or(steering.path)
+    }
+}
+
+/// Behaviors enemy kinds can pick from, by name.
+///
+/// Starts with the built-in ones, and apps can register their own before
+/// loading enemy tables that use them.
+#[derive(Resource)]
+pub struct EnemyBehaviors(HashMap<String, Box<dyn Behavior>>);
+
+impl EnemyBehaviors {
+    /// No behaviors at all
+    pub fn empty() -> Self {
+        Self(HashMap::default())
+    }
+
+    /// Adds `behavior` under `name`, replacing any behavior already there
+    pub fn register(&mut self, name: impl Into<String>, behavior: impl Behavior + 'static) {
+        self.0.insert(name.into(), Box::new(behavior));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Behavior> {
+        self.0.get(name).map(|behavior| behavior.as_ref())
+    }
+
+    // Why `name` can't be used, if it can't
+    fn problem(&self, name: &str) -> Option<String> {
+        match self.get(name) {
+            Some(behavior) => behavior
+                .problem()
+                .map(|problem| format!("behavior {name:?} {problem}")),
+            None => Some(format!(
+                "unknown behavior {name:?}, expected one of {:?}",
+                self.names()
+            )),
+        }
+    }
+
+    /// Names of every behavior, in alphabetical order
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.0.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Checks that every kind in `table` uses a registered behavior without
+    /// problems
+    pub fn check(&self, table: &EnemyTable) -> Result<(), EnemyTableError> {
+        match table
+            .kinds
+            .iter()
+            .find_map(|kind| Some((kind, self.problem(&kind.behavior)?)))
+        {
+            Some((kind, problem)) => Err(EnemyTableError::Invalid(format!(
+                "{} has {problem}",
+                kind.name
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks that every phase of the bosses in `script` uses a registered
+    /// behavior without problems
+    pub fn check_bosses(&self, script: &WaveScript) -> Result<(), WaveScriptError> {
+        let problem = script.bosses.iter().find_map(|boss| {
+            let problem = boss
+                .phases
+                .iter()
+                .find_map(|phase| self.problem(&phase.behavior))?;
+            Some((boss, problem))
+        });
+        match problem {
+            Some((boss, problem)) => Err(WaveScriptError::Invalid(format!(
+                "boss {} has {problem}",
+                boss.name
+            ))),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Default for EnemyBehaviors {
+    fn default() -> Self {
+        let mut behaviors = Self::empty();
+        behaviors.register("chase", Chase);
+        behaviors.register("zigzag", Zigzag::default());
+        behaviors.register("orbit", Orbit::default());
+        behaviors.register("dash", Dash::default());
+        behaviors.register("wander", Wander::default());
+        behaviors.register("flee", Flee::default());
+        behaviors.register("intercept", Intercept::default());
+        behaviors
+    }
+}
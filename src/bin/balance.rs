@@ -0,0 +1,167 @@
+//! Plays headless games with a bot and reports statistics about every run.
+//!
+//! `cargo run --release --bin balance -- --games 20 --seed 0 --bot kite` plays
+//! 20 games with seeds 0 to 19. The options are:
+//!
+//! * `--games <n>`: number of games, 10 by default
+//! * `--seed <n>`: seed of the first game, 0 by default
+//! * `--seeds <a,b,...>`: exact seeds to play, instead of `--games` and `--seed`
+//! * `--bot <name>`: bot playing the games, `kite` by default
+//! * `--max-time <secs>`: game time after which a run is cut short, 600 by default
+//! * `--format <csv|json>`: report format, `csv` by default
+//! * `--output <path>`: file the report is written to, stdout by default
+//!
+//! Build with `--features mobile` to measure the mobile balance instead.
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    process,
+};
+
+use bevy_shoot_em_up::{bot_by_name, AppState, DeathCause, Simulation, BOT_NAMES};
+use serde::Serialize;
+
+#[derive(Serialize)]
+struct Run {
+    variant: &'static str,
+    seed: u64,
+    bot: String,
+    survival_time: f32,
+    kills: u32,
+    shots_fired: u32,
+    accuracy: f32,
+    max_concurrent_enemies: usize,
+    /// Empty when the run reached `--max-time` alive
+    death_cause: Option<DeathCause>,
+    death_x: Option<f32>,
+    death_y: Option<f32>,
+}
+
+const CSV_HEADER: &str = "variant,seed,bot,survival_time,kills,shots_fired,accuracy,max_concurrent_enemies,death_cause,death_x,death_y";
+
+impl Run {
+    fn csv_row(&self) -> String {
+        let optional = |value: Option<String>| value.unwrap_or_default();
+        format!(
+            "{},{},{},{},{},{},{},{},{},{},{}",
+            self.variant,
+            self.seed,
+            self.bot,
+            self.survival_time,
+            self.kills,
+            self.shots_fired,
+            self.accuracy,
+            self.max_concurrent_enemies,
+            optional(self.death_cause.map(|cause| cause.to_string())),
+            optional(self.death_x.map(|x| x.to_string())),
+            optional(self.death_y.map(|y| y.to_string())),
+        )
+    }
+}
+
+// Value following `name` on the command line
+fn arg(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+    }
+    None
+}
+
+fn parsed_arg<T: std::str::FromStr>(name: &str, default: T) -> T {
+    match arg(name) {
+        Some(value) => value
+            .parse()
+            .unwrap_or_else(|_| fail(&format!("invalid {name}: {value}"))),
+        None => default,
+    }
+}
+
+fn fail(message: &str) -> ! {
+    eprintln!("{message}");
+    process::exit(1);
+}
+
+fn seeds() -> Vec<u64> {
+    match arg("--seeds") {
+        Some(seeds) => seeds
+            .split(',')
+            .map(|seed| {
+                seed.trim()
+                    .parse()
+                    .unwrap_or_else(|_| fail(&format!("invalid seed: {seed}")))
+            })
+            .collect(),
+        None => {
+            let first = parsed_arg("--seed", 0);
+            (first..first + parsed_arg("--games", 10)).collect()
+        }
+    }
+}
+
+fn play(bot: &str, seed: u64, max_time: f32) -> Run {
+    let mut simulation = Simulation::new(seed);
+    simulation.set_bot(bot_by_name(bot).unwrap());
+    while simulation.state() == AppState::InGame && simulation.run_stats().survival_time < max_time
+    {
+        simulation.tick();
+    }
+
+    let stats = simulation.run_stats();
+    Run {
+        variant: if cfg!(feature = "mobile") {
+            "mobile"
+        } else {
+            "desktop"
+        },
+        seed,
+        bot: bot.to_string(),
+        survival_time: stats.survival_time,
+        kills: stats.kills,
+        shots_fired: stats.shots_fired,
+        accuracy: stats.accuracy(),
+        max_concurrent_enemies: stats.max_concurrent_enemies,
+        death_cause: stats.death.as_ref().map(|death| death.cause),
+        death_x: stats.death.as_ref().map(|death| death.position[0]),
+        death_y: stats.death.as_ref().map(|death| death.position[1]),
+    }
+}
+
+fn main() -> io::Result<()> {
+    let bot = arg("--bot").unwrap_or_else(|| "kite".to_string());
+    if bot_by_name(&bot).is_none() {
+        fail(&format!(
+            "unknown bot {bot}, expected one of {}",
+            BOT_NAMES.join(", ")
+        ));
+    }
+    let max_time = parsed_arg("--max-time", 600.);
+    let format = arg("--format").unwrap_or_else(|| "csv".to_string());
+    if format != "csv" && format != "json" {
+        fail(&format!("unknown format {format}, expected csv or json"));
+    }
+
+    let mut output: Box<dyn Write> = match arg("--output") {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+
+    let runs: Vec<Run> = seeds()
+        .into_iter()
+        .map(|seed| play(&bot, seed, max_time))
+        .collect();
+
+    if format == "json" {
+        serde_json::to_writer_pretty(&mut output, &runs)?;
+        writeln!(output)?;
+    } else {
+        writeln!(output, "{CSV_HEADER}")?;
+        for run in &runs {
+            writeln!(output, "{}", run.csv_row())?;
+        }
+    }
+    output.flush()
+}
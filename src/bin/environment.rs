@@ -0,0 +1,71 @@
+//! Exposes the game as a step-based environment over stdin and stdout.
+//!
+//! Every line on stdin is a JSON request, answered by one JSON line on stdout:
+//!
+//! * `{"cmd": "reset", "seed": 42}` starts a new episode (the seed is optional)
+//!   and answers with `{"observation": ...}`
+//! * `{"cmd": "step", "action": {"movement": {"x": 1, "y": 0}, "fire": {"x": 0, "y": 1}}}`
+//!   runs one tick and answers with `{"observation": ..., "reward": ..., "done": ...}`
+//!
+//! Directions longer than 1 are shortened to unit length. Malformed requests,
+//! and actions with directions that aren't finite numbers, are answered with
+//! `{"error": "..."}`.
+
+use std::io::{self, BufRead, Write};
+
+use bevy_shoot_em_up::{Environment, Observation, PlayerInput, Step};
+use serde::{Deserialize, Serialize};
+
+#[derive(Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+enum Request {
+    Reset { seed: Option<u64> },
+    Step { action: PlayerInput },
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum Response {
+    Reset { observation: Observation },
+    Step(Step),
+    Error { error: String },
+}
+
+fn main() -> io::Result<()> {
+    let mut environment: Option<Environment> = None;
+    let mut stdout = io::stdout().lock();
+
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str(&line) {
+            Ok(Request::Reset { seed }) => {
+                let seed = seed.unwrap_or_else(rand::random);
+                let environment = environment.insert(Environment::new(seed));
+                Response::Reset {
+                    observation: environment.simulation().observe(),
+                }
+            }
+            Ok(Request::Step { action }) => match environment.as_mut() {
+                Some(environment) => match environment.step(action) {
+                    Ok(step) => Response::Step(step),
+                    Err(err) => Response::Error {
+                        error: err.to_string(),
+                    },
+                },
+                None => Response::Error {
+                    error: "reset must be called before step".to_string(),
+                },
+            },
+            Err(err) => Response::Error {
+                error: err.to_string(),
+            },
+        };
+        serde_json::to_writer(&mut stdout, &response)?;
+        writeln!(stdout)?;
+        stdout.flush()?;
+    }
+    Ok(())
+}
@@ -0,0 +1,231 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{BehaviorState, EnemyFire, EnemyGun, EnemyKind, Health, ENEMY_SIZE};
+
+const BOSS_BAR_COLOR: Color = Color::rgb(0.85, 0.1, 0.1);
+const BOSS_BAR_BACKGROUND: Color = Color::rgba(0.2, 0.2, 0.2, 0.8);
+const BOSS_NAME_FONT_SIZE: f32 = 24.0;
+
+/// Stretch of a boss fight with its own way of moving and shooting
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BossPhase {
+    /// Fraction of its health the boss has left when the phase starts
+    pub from: f32,
+    /// Name of the way it moves, one of the
+    /// [`EnemyBehaviors`](crate::EnemyBehaviors)
+    pub behavior: String,
+    /// Multiple of the base enemy speed
+    pub speed: f32,
+    #[serde(default)]
+    pub fire: Option<EnemyFire>,
+}
+
+/// Chasing the player at the base enemy speed from full health on
+impl Default for BossPhase {
+    fn default() -> Self {
+        Self {
+            from: 1.,
+            behavior: "chase".into(),
+            speed: 1.,
+            fire: None,
+        }
+    }
+}
+
+/// A large enemy fought on its own, changing phases as it is worn down
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BossKind {
+    pub name: String,
+    pub size: f32,
+    /// Red, green and blue, from 0 to 1
+    pub color: [f32; 3],
+    pub hit_points: u32,
+    /// Points scored for defeating it
+    pub score: u32,
+    /// From the first to the last, starting at full health
+    pub phases: Vec<BossPhase>,
+}
+
+/// A boss twice the size of a plain enemy, chasing the player in a single
+/// phase, to build other bosses from
+impl Default for BossKind {
+    fn default() -> Self {
+        Self {
+            name: "boss".into(),
+            size: 2. * ENEMY_SIZE,
+            color: [1., 1., 1.],
+            hit_points: 20,
+            score: 20,
+            phases: vec![BossPhase::default()],
+        }
+    }
+}
+
+impl BossKind {
+    /// Reason the boss can't be fought, if any
+    pub(crate) fn problem(&self) -> Option<String> {
+        let name = &self.name;
+        if self.size <= 0. || self.hit_points == 0 {
+            return Some(format!("boss {name} needs a size and hit points"));
+        }
+        if self.phases.first().map(|phase| phase.from) != Some(1.) {
+            return Some(format!("boss {name} needs a first phase from 1.0"));
+        }
+        if self
+            .phases
+            .windows(2)
+            .any(|pair| pair[1].from >= pair[0].from || pair[1].from <= 0.)
+        {
+            return Some(format!(
+                "boss {name} needs phases from less and less health, above 0"
+            ));
+        }
+        self.phases.iter().find_map(|phase| {
+            let fire = phase.fire.as_ref()?;
+            fire.problem()
+                .map(|problem| format!("boss {name} {problem}"))
+        })
+    }
+
+    /// The boss as an enemy kind, moving and shooting as in phase `phase`
+    pub fn kind(&self, phase: usize) -> EnemyKind {
+        let phase = &self.phases[phase];
+        EnemyKind {
+            name: self.name.clone(),
+            // Never picked at random
+            weight: 0.,
+            size: self.size,
+            color: self.color,
+            speed: phase.speed,
+            speed_ramp: 0.,
+            hit_points: self.hit_points,
+            score: self.score,
+            behavior: phase.behavior.clone(),
+            fire: phase.fire.clone(),
+            split: None,
+        }
+    }
+
+    /// Phase the boss is in with `health` of its health left
+    pub fn phase_at(&self, health: f32) -> usize {
+        self.phases
+            .iter()
+            .rposition(|phase| phase.from >= health)
+            .unwrap_or(0)
+    }
+}
+
+/// Marks the enemy being fought as a boss, and the phase it is in
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Boss {
+    pub kind: BossKind,
+    pub phase: usize,
+}
+
+/// Moves bosses on to the phase their health calls for, taking up its way of
+/// moving and shooting from scratch
+pub(crate) fn advance_boss_phases(
+    mut query: Query<(
+        &Health,
+        &mut Boss,
+        &mut EnemyKind,
+        &mut EnemyGun,
+        &mut BehaviorState,
+    )>,
+) {
+    for (health, mut boss, mut kind, mut gun, mut behavior_state) in query.iter_mut() {
+        let phase = boss.kind.phase_at(health.fraction());
+        if phase <= boss.phase {
+            continue;
+        }
+        boss.phase = phase;
+        *kind = boss.kind.kind(phase);
+        *gun = EnemyGun::new(&kind);
+        *behavior_state = BehaviorState::default();
+    }
+}
+
+/// Health bar of the boss being fought, along the top of the screen
+#[derive(Component)]
+pub struct BossHealthBar;
+
+#[derive(Component)]
+pub struct BossHealthFill;
+
+#[derive(Component)]
+pub struct BossName;
+
+pub fn setup_boss_health_bar(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Percent(8.),
+                    left: Val::Percent(20.),
+                    width: Val::Percent(60.),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            BossHealthBar,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/RusticBlackShadow.ttf"),
+                        font_size: BOSS_NAME_FONT_SIZE,
+                        color: BOSS_BAR_COLOR,
+                    },
+                ),
+                BossName,
+            ));
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        height: Val::Px(12.),
+                        ..default()
+                    },
+                    background_color: BOSS_BAR_BACKGROUND.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        NodeBundle {
+                            style: Style {
+                                width: Val::Percent(100.),
+                                height: Val::Percent(100.),
+                                ..default()
+                            },
+                            background_color: BOSS_BAR_COLOR.into(),
+                            ..default()
+                        },
+                        BossHealthFill,
+                    ));
+                });
+        });
+}
+
+/// Shows the bar while there is a boss to fight
+pub fn update_boss_health_bar(
+    boss_query: Query<(&Boss, &Health)>,
+    mut bar_query: Query<&mut Visibility, With<BossHealthBar>>,
+    mut fill_query: Query<&mut Style, With<BossHealthFill>>,
+    mut name_query: Query<&mut Text, With<BossName>>,
+) {
+    let mut visibility = bar_query.single_mut();
+    let Some((boss, health)) = boss_query.iter().next() else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Inherited;
+    fill_query.single_mut().width = Val::Percent(health.fraction() * 100.);
+    name_query.single_mut().sections[0].value = boss.kind.name.clone();
+}
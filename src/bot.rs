@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+use rand::Rng as _;
+use rand_pcg::Pcg32;
+
+use crate::{
+    normalize_direction, observation::Observer, Direction, GameRng, Observation, PlayerInput,
+    RngStream, PLAYER_BOTTOM_BOUNDARY, PLAYER_LEFT_BOUNDARY, PLAYER_RIGHT_BOUNDARY,
+    PLAYER_TOP_BOUNDARY,
+};
+
+/// An automated player, deciding the input for every tick from what it sees.
+///
+/// Bots are free to fire in any direction, not only the ones the keyboard
+/// allows. Randomness must come from `rng` for games to stay reproducible.
+pub trait Bot: Send + Sync {
+    fn act(&mut self, observation: &Observation, rng: &mut Pcg32) -> PlayerInput;
+}
+
+/// The bot playing while [`InputSource::Bot`](crate::InputSource::Bot) is selected
+#[derive(Resource)]
+pub struct ActiveBot(pub Box<dyn Bot>);
+
+/// Names accepted by [`bot_by_name`]
+pub const BOT_NAMES: [&str; 3] = ["random", "kite", "corner"];
+
+pub fn bot_by_name(name: &str) -> Option<Box<dyn Bot>> {
+    match name {
+        "random" => Some(Box::<RandomBot>::default()),
+        "kite" => Some(Box::<KiteBot>::default()),
+        "corner" => Some(Box::<CornerBot>::default()),
+        _ => None,
+    }
+}
+
+pub fn drive_bot(
+    observer: Observer,
+    mut bot: ResMut<ActiveBot>,
+    mut rng: ResMut<GameRng>,
+    mut player_input: ResMut<PlayerInput>,
+) {
+    *player_input = bot
+        .0
+        .act(&observer.observation(), rng.stream(RngStream::Bot));
+}
+
+fn direction(vector: Vec2) -> Direction {
+    let mut direction = Direction {
+        x: vector.x,
+        y: vector.y,
+    };
+    normalize_direction(&mut direction);
+    direction
+}
+
+fn nearest_enemy(observation: &Observation, player: Vec2) -> Option<Vec2> {
+    observation
+        .enemies
+        .iter()
+        .map(|enemy| Vec2::from(enemy.position))
+        .min_by(|a, b| {
+            a.distance_squared(player)
+                .total_cmp(&b.distance_squared(player))
+        })
+}
+
+/// Fires in the direction of the nearest enemy, if there is one
+fn aim_at_nearest_enemy(observation: &Observation, player: Vec2) -> Direction {
+    nearest_enemy(observation, player)
+        .map(|enemy| direction(enemy - player))
+        .unwrap_or_default()
+}
+
+/// Moves and fires in random directions, keeping each choice for a while
+#[derive(Default)]
+pub struct RandomBot {
+    input: PlayerInput,
+    ticks_left: u32,
+}
+
+impl Bot for RandomBot {
+    fn act(&mut self, _observation: &Observation, rng: &mut Pcg32) -> PlayerInput {
+        if self.ticks_left == 0 {
+            let mut random_direction = || {
+                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                Direction {
+                    x: angle.cos(),
+                    y: angle.sin(),
+                }
+            };
+            self.input = PlayerInput {
+                movement: random_direction(),
+                fire: random_direction(),
+            };
+            self.ticks_left = rng.gen_range(10..40);
+        }
+        self.ticks_left -= 1;
+        self.input.clone()
+    }
+}
+
+/// Shoots the nearest enemy while running away from the enemies close by,
+/// drifting back to the middle of the arena when none are.
+pub struct KiteBot {
+    /// Enemies closer than this are run away from
+    pub danger_radius: f32,
+}
+
+impl Default for KiteBot {
+    fn default() -> Self {
+        Self {
+            danger_radius: 200.,
+        }
+    }
+}
+
+impl Bot for KiteBot {
+    fn act(&mut self, observation: &Observation, _rng: &mut Pcg32) -> PlayerInput {
+        let Some(player) = observation.player.map(Vec2::from) else {
+            return PlayerInput::default();
+        };
+
+        let mut flee = Vec2::ZERO;
+        for enemy in &observation.enemies {
+            let away = player - Vec2::from(enemy.position);
+            let distance = away.length();
+            if distance < self.danger_radius {
+                // The closer the enemy, the stronger the push
+                flee += away.normalize_or_zero() * (1. - distance / self.danger_radius);
+            }
+        }
+
+        let center = Vec2::new(
+            (PLAYER_LEFT_BOUNDARY + PLAYER_RIGHT_BOUNDARY) / 2.,
+            (PLAYER_BOTTOM_BOUNDARY + PLAYER_TOP_BOUNDARY) / 2.,
+        );
+        let half_size = Vec2::new(
+            (PLAYER_RIGHT_BOUNDARY - PLAYER_LEFT_BOUNDARY) / 2.,
+            (PLAYER_TOP_BOUNDARY - PLAYER_BOTTOM_BOUNDARY) / 2.,
+        );
+        // Being pushed into a wall is how kiting goes wrong, so the pull back to
+        // the middle grows quickly near the walls
+        let to_center = (center - player) / half_size;
+        let movement = flee * 2. + to_center * to_center.length().powi(3);
+
+        PlayerInput {
+            movement: if movement.length() > 0.05 {
+                direction(movement)
+            } else {
+                Direction::default()
+            },
+            fire: aim_at_nearest_enemy(observation, player),
+        }
+    }
+}
+
+/// Retreats to a bottom corner of the arena and shoots the nearest enemy from there
+#[derive(Default)]
+pub struct CornerBot {
+    pub right: bool,
+}
+
+impl Bot for CornerBot {
+    fn act(&mut self, observation: &Observation, _rng: &mut Pcg32) -> PlayerInput {
+        let Some(player) = observation.player.map(Vec2::from) else {
+            return PlayerInput::default();
+        };
+        let corner = Vec2::new(
+            if self.right {
+                PLAYER_RIGHT_BOUNDARY
+            } else {
+                PLAYER_LEFT_BOUNDARY
+            },
+            PLAYER_BOTTOM_BOUNDARY,
+        );
+        let to_corner = corner - player;
+
+        PlayerInput {
+            movement: if to_corner.length() > 1. {
+                direction(to_corner)
+            } else {
+                Direction::default()
+            },
+            fire: aim_at_nearest_enemy(observation, player),
+        }
+    }
+}
@@ -0,0 +1,320 @@
+use bevy::prelude::*;
+
+use crate::WALL_THICKNESS;
+
+/// Hitbox of an entity, in world units around its translation.
+///
+/// Shapes don't rotate or follow the transform's scale, which only sizes the
+/// mesh the entity is drawn with.
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub enum CollisionShape {
+    Circle {
+        radius: f32,
+    },
+    Box {
+        half_size: Vec2,
+    },
+    /// Every point within `radius` of the segment from `-half_segment` to
+    /// `half_segment`
+    Capsule {
+        half_segment: Vec2,
+        radius: f32,
+    },
+}
+
+impl CollisionShape {
+    /// Half size of the smallest box around the shape
+    pub fn half_extents(&self) -> Vec2 {
+        match *self {
+            CollisionShape::Circle { radius } => Vec2::splat(radius),
+            CollisionShape::Box { half_size } => half_size,
+            CollisionShape::Capsule {
+                half_segment,
+                radius,
+            } => half_segment.abs() + radius,
+        }
+    }
+
+    /// Shape covering everything this one touches while moving from `start` to
+    /// `end`, with the position to place it at.
+    ///
+    /// Away from the ends it is as wide as the shape's widest side, so a box
+    /// sweeps out slightly more than it really does.
+    pub fn swept(&self, start: Vec2, end: Vec2) -> (Vec2, CollisionShape) {
+        let center = (start + end) / 2.;
+        if start == end {
+            return (center, *self);
+        }
+        let half_segment = (end - start) / 2.;
+        let shape = match *self {
+            CollisionShape::Circle { radius } => CollisionShape::Capsule {
+                half_segment,
+                radius,
+            },
+            CollisionShape::Box { half_size } => CollisionShape::Capsule {
+                half_segment,
+                radius: half_size.max_element(),
+            },
+            CollisionShape::Capsule {
+                half_segment: own_half_segment,
+                radius,
+            } => CollisionShape::Capsule {
+                half_segment,
+                radius: radius + own_half_segment.length(),
+            },
+        };
+        (center, shape)
+    }
+
+    /// Smallest move getting the shape at `position` out of the box at
+    /// `box_center`, or zero if they don't overlap.
+    ///
+    /// Capsules are pushed out as if they were their bounding box.
+    pub fn push_out_of_box(&self, position: Vec2, box_center: Vec2, box_half_size: Vec2) -> Vec2 {
+        let offset = position - box_center;
+        if let CollisionShape::Circle { radius } = *self {
+            let closest = position.clamp(box_center - box_half_size, box_center + box_half_size);
+            let outward = position - closest;
+            let distance = outward.length();
+            if distance > 0. {
+                return if distance < radius {
+                    outward / distance * (radius - distance)
+                } else {
+                    Vec2::ZERO
+                };
+            }
+        }
+        // The center is inside the box, or the shape is boxy itself: leave
+        // through the closest side
+        let overlap = self.half_extents() + box_half_size - offset.abs();
+        if overlap.x <= 0. || overlap.y <= 0. {
+            return Vec2::ZERO;
+        }
+        let sign = |value: f32| if value < 0. { -1. } else { 1. };
+        if overlap.x < overlap.y {
+            Vec2::new(overlap.x * sign(offset.x), 0.)
+        } else {
+            Vec2::new(0., overlap.y * sign(offset.y))
+        }
+    }
+
+    /// Whether the shape at `position` overlaps `other` at `other_position`
+    pub fn intersects(&self, position: Vec2, other: &CollisionShape, other_position: Vec2) -> bool {
+        use CollisionShape::*;
+
+        match (*self, *other) {
+            (
+                Circle { radius },
+                Circle {
+                    radius: other_radius,
+                },
+            ) => position.distance_squared(other_position) <= (radius + other_radius).powi(2),
+            (Circle { radius }, Box { half_size }) | (Box { half_size }, Circle { radius }) => {
+                let (circle, rectangle) = if matches!(self, Circle { .. }) {
+                    (position, other_position)
+                } else {
+                    (other_position, position)
+                };
+                box_distance_squared(circle, rectangle, half_size) <= radius.powi(2)
+            }
+            (
+                Box { half_size },
+                Box {
+                    half_size: other_half_size,
+                },
+            ) => {
+                let gap = (position - other_position).abs() - half_size - other_half_size;
+                gap.x <= 0. && gap.y <= 0.
+            }
+            (
+                Capsule {
+                    half_segment,
+                    radius,
+                },
+                other_shape,
+            ) => other_shape.intersects_segment(
+                other_position,
+                position - half_segment,
+                position + half_segment,
+                radius,
+            ),
+            (
+                other_shape,
+                Capsule {
+                    half_segment,
+                    radius,
+                },
+            ) => other_shape.intersects_segment(
+                position,
+                other_position - half_segment,
+                other_position + half_segment,
+                radius,
+            ),
+        }
+    }
+
+    // Whether the shape at `position` comes within `radius` of the segment
+    // from `start` to `end`
+    fn intersects_segment(&self, position: Vec2, start: Vec2, end: Vec2, radius: f32) -> bool {
+        match *self {
+            CollisionShape::Circle {
+                radius: circle_radius,
+            } => segment_distance_squared(position, start, end) <= (radius + circle_radius).powi(2),
+            CollisionShape::Box { half_size } => {
+                let min = position - half_size;
+                let max = position + half_size;
+                if segment_crosses_box(start, end, min, max) {
+                    return true;
+                }
+                // Otherwise the closest points of two convex shapes include a
+                // corner of one of them
+                let corners = [min, Vec2::new(min.x, max.y), max, Vec2::new(max.x, min.y)];
+                let distance_squared = corners
+                    .iter()
+                    .map(|&corner| segment_distance_squared(corner, start, end))
+                    .chain(
+                        [start, end]
+                            .iter()
+                            .map(|&point| box_distance_squared(point, position, half_size)),
+                    )
+                    .fold(f32::INFINITY, f32::min);
+                distance_squared <= radius.powi(2)
+            }
+            CollisionShape::Capsule {
+                half_segment,
+                radius: capsule_radius,
+            } => {
+                let other_start = position - half_segment;
+                let other_end = position + half_segment;
+                if segments_cross(start, end, other_start, other_end) {
+                    return true;
+                }
+                let distance_squared = [
+                    segment_distance_squared(start, other_start, other_end),
+                    segment_distance_squared(end, other_start, other_end),
+                    segment_distance_squared(other_start, start, end),
+                    segment_distance_squared(other_end, start, end),
+                ]
+                .into_iter()
+                .fold(f32::INFINITY, f32::min);
+                distance_squared <= (radius + capsule_radius).powi(2)
+            }
+        }
+    }
+}
+
+fn box_distance_squared(point: Vec2, center: Vec2, half_size: Vec2) -> f32 {
+    let closest = point.clamp(center - half_size, center + half_size);
+    point.distance_squared(closest)
+}
+
+fn segment_distance_squared(point: Vec2, start: Vec2, end: Vec2) -> f32 {
+    let segment = end - start;
+    let length_squared = segment.length_squared();
+    let t = if length_squared > 0. {
+        ((point - start).dot(segment) / length_squared).clamp(0., 1.)
+    } else {
+        0.
+    };
+    point.distance_squared(start + segment * t)
+}
+
+// Clips the segment against both slabs of the box
+fn segment_crosses_box(start: Vec2, end: Vec2, min: Vec2, max: Vec2) -> bool {
+    let direction = end - start;
+    let mut enter = 0f32;
+    let mut exit = 1f32;
+    for axis in 0..2 {
+        if direction[axis] == 0. {
+            if start[axis] < min[axis] || start[axis] > max[axis] {
+                return false;
+            }
+        } else {
+            let t1 = (min[axis] - start[axis]) / direction[axis];
+            let t2 = (max[axis] - start[axis]) / direction[axis];
+            enter = enter.max(t1.min(t2));
+            exit = exit.min(t1.max(t2));
+            if enter > exit {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+fn segments_cross(a_start: Vec2, a_end: Vec2, b_start: Vec2, b_end: Vec2) -> bool {
+    let a = a_end - a_start;
+    let b = b_end - b_start;
+    let denominator = a.perp_dot(b);
+    if denominator == 0. {
+        // Parallel segments only touch when their ends are on each other,
+        // which the endpoint distances already cover
+        return false;
+    }
+    let offset = b_start - a_start;
+    let t = offset.perp_dot(b) / denominator;
+    let u = offset.perp_dot(a) / denominator;
+    (0. ..=1.).contains(&t) && (0. ..=1.).contains(&u)
+}
+
+/// Longest distance moved between two checks against the walls, short enough
+/// to never skip over a wall
+const MAX_WALL_STEP: f32 = WALL_THICKNESS / 2.;
+
+/// First wall a shape ran into while moving
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct WallHit {
+    /// Pointing away from the wall
+    pub normal: Vec2,
+    /// Index of the wall in the slice moved against
+    pub wall: usize,
+}
+
+/// Moves the shape from `position` by `movement`, keeping it out of the
+/// `walls`, given as box centers and half sizes.
+///
+/// Returns where the shape ends up and the first wall it ran into, if any.
+/// With `stop_on_hit` the shape stops right there, otherwise it keeps sliding
+/// along the walls.
+pub(crate) fn move_against_walls(
+    shape: &CollisionShape,
+    mut position: Vec2,
+    movement: Vec2,
+    walls: &[(Vec2, Vec2)],
+    stop_on_hit: bool,
+) -> (Vec2, Option<WallHit>) {
+    let steps = (movement.length() / MAX_WALL_STEP).ceil().max(1.);
+    let step = movement / steps;
+    let mut hit = None;
+    for _ in 0..steps as u32 {
+        position += step;
+        for (wall, &(wall_center, wall_half_size)) in walls.iter().enumerate() {
+            let push = shape.push_out_of_box(position, wall_center, wall_half_size);
+            if push != Vec2::ZERO {
+                position += push;
+                hit = hit.or(Some(WallHit {
+                    normal: push.normalize(),
+                    wall,
+                }));
+            }
+        }
+        if hit.is_some() && stop_on_hit {
+            break;
+        }
+    }
+    (position, hit)
+}
+
+/// Whether the shape can move in a straight line from `start` to `end`
+/// without touching any of the `walls`
+pub(crate) fn clear_path(
+    shape: &CollisionShape,
+    start: Vec2,
+    end: Vec2,
+    walls: &[(Vec2, Vec2)],
+) -> bool {
+    let (center, swept) = shape.swept(start, end);
+    !walls.iter().any(|&(wall_center, half_size)| {
+        swept.intersects(center, &CollisionShape::Box { half_size }, wall_center)
+    })
+}
@@ -0,0 +1,200 @@
+use std::f32::consts::TAU;
+
+use bevy::{prelude::*, utils::HashSet};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    Bullet, CollisionShape, Direction, Enemy, EnemyBullet, EnemyKind, Player, PreviousPosition,
+    RemainingBounces,
+};
+
+/// Diameter of the round bullets enemies fire
+pub const ENEMY_BULLET_SIZE: f32 = 8.0;
+
+/// How an enemy kind shoots at the player
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnemyFire {
+    pub pattern: FirePattern,
+    /// Seconds between volleys, also waited before the first one
+    pub cooldown: f32,
+}
+
+impl EnemyFire {
+    /// What keeps the enemy from firing, if anything
+    pub(crate) fn problem(&self) -> Option<&'static str> {
+        if !self.cooldown.is_finite() || self.cooldown <= 0. {
+            return Some("has no fire cooldown");
+        }
+        match self.pattern {
+            FirePattern::Radial { bullets: 0 } | FirePattern::Spiral { bullets: 0, .. } => {
+                Some("fires no bullets")
+            }
+            FirePattern::Spiral { turn, .. } if !turn.is_finite() => {
+                Some("turns by a number that isn't finite")
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Directions the bullets of a volley fly in
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum FirePattern {
+    /// A single bullet straight at the player
+    Aimed,
+    /// `bullets` bullets spread evenly all around the enemy
+    Radial { bullets: u32 },
+    /// Like `Radial`, turned by another `turn` radians every volley
+    Spiral { bullets: u32, turn: f32 },
+}
+
+impl FirePattern {
+    /// Directions of one volley, fired `to_player` away from the player by a
+    /// gun turned `spin` radians so far
+    pub fn directions(&self, to_player: Vec2, spin: f32) -> Vec<Vec2> {
+        let spread = |bullets: u32, start: f32| {
+            (0..bullets)
+                .map(|bullet| Vec2::from_angle(start + TAU * bullet as f32 / bullets as f32))
+                .collect()
+        };
+        match *self {
+            FirePattern::Aimed => match to_player.try_normalize() {
+                Some(direction) => vec![direction],
+                None => Vec::new(),
+            },
+            FirePattern::Radial { bullets } => spread(bullets, 0.),
+            FirePattern::Spiral { bullets, .. } => spread(bullets, spin),
+        }
+    }
+}
+
+/// State of an enemy's firing, between volleys
+#[derive(Component, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnemyGun {
+    /// Seconds until the next volley
+    pub reload: f32,
+    /// Radians a spiral has turned so far
+    pub spin: f32,
+}
+
+impl EnemyGun {
+    pub fn new(kind: &EnemyKind) -> Self {
+        Self {
+            reload: kind.fire.as_ref().map_or(0., |fire| fire.cooldown),
+            spin: 0.,
+        }
+    }
+}
+
+/// Whether the player's bullets destroy enemy bullets they run into, off by
+/// default
+#[derive(Resource, Default, Clone, Debug, PartialEq)]
+pub struct BulletCancel {
+    pub enabled: bool,
+}
+
+pub(crate) fn enemy_bullet_bundle(
+    translation: Vec3,
+    direction: Direction,
+) -> (
+    Direction,
+    SpatialBundle,
+    CollisionShape,
+    RemainingBounces,
+    PreviousPosition,
+    EnemyBullet,
+) {
+    (
+        direction,
+        SpatialBundle::from_transform(
+            Transform::from_translation(translation).with_scale(Vec3::splat(ENEMY_BULLET_SIZE)),
+        ),
+        CollisionShape::Circle {
+            radius: ENEMY_BULLET_SIZE / 2.,
+        },
+        // Enemy bullets never ricochet
+        RemainingBounces(0),
+        PreviousPosition(translation.truncate()),
+        EnemyBullet,
+    )
+}
+
+pub(crate) fn fire_enemy_bullets(
+    mut commands: Commands,
+    player_query: Query<&Transform, With<Player>>,
+    mut enemy_query: Query<(&Transform, &EnemyKind, &mut EnemyGun), With<Enemy>>,
+    time: Res<Time>,
+) {
+    let Ok(player_transform) = player_query.get_single() else {
+        return;
+    };
+    let player_position = player_transform.translation.truncate();
+    for (transform, kind, mut gun) in enemy_query.iter_mut() {
+        let Some(fire) = &kind.fire else {
+            continue;
+        };
+        gun.reload -= time.delta_seconds();
+        if gun.reload > 0. {
+            continue;
+        }
+        gun.reload += fire.cooldown;
+
+        let position = transform.translation.truncate();
+        for direction in fire
+            .pattern
+            .directions(player_position - position, gun.spin)
+        {
+            // Fired from the edge of the enemy
+            let origin = position + direction * kind.size / 2.;
+            commands.spawn(enemy_bullet_bundle(
+                origin.extend(transform.translation.z),
+                Direction {
+                    x: direction.x,
+                    y: direction.y,
+                },
+            ));
+        }
+        if let FirePattern::Spiral { turn, .. } = fire.pattern {
+            gun.spin = (gun.spin + turn) % TAU;
+        }
+    }
+}
+
+type CancellingBullet = (
+    Entity,
+    &'static Transform,
+    &'static PreviousPosition,
+    &'static CollisionShape,
+);
+
+// Runs after both kinds of bullets moved, with their paths compared as seen
+// from the enemy bullet, so that bullets flying head-on can't pass through
+// each other in one tick
+pub(crate) fn cancel_bullets(
+    mut commands: Commands,
+    bullet_query: Query<CancellingBullet, With<Bullet>>,
+    enemy_bullet_query: Query<CancellingBullet, With<EnemyBullet>>,
+) {
+    let mut cancelled = HashSet::new();
+    for (bullet, transform, previous_position, shape) in bullet_query.iter() {
+        let position = transform.translation.truncate();
+        let hit = enemy_bullet_query.iter().find(
+            |(enemy_bullet, enemy_transform, enemy_previous_position, enemy_shape)| {
+                if cancelled.contains(enemy_bullet) {
+                    return false;
+                }
+                let enemy_position = enemy_transform.translation.truncate();
+                let (offset, swept) = shape.swept(
+                    previous_position.0 - enemy_previous_position.0,
+                    position - enemy_position,
+                );
+                swept.intersects(offset, enemy_shape, Vec2::ZERO)
+            },
+        );
+        if let Some((enemy_bullet, ..)) = hit {
+            cancelled.insert(enemy_bullet);
+            commands.entity(bullet).despawn();
+            commands.entity(enemy_bullet).despawn();
+        }
+    }
+}
@@ -0,0 +1,291 @@
+use std::{error::Error, fmt, io};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use rand::Rng as _;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+use crate::{EnemyBehaviors, EnemyFire, ENEMY_SIZE};
+
+/// Asset the enemy kinds are loaded from, and hot reloaded with the
+/// `hot-reload` feature
+pub const ENEMY_TABLE_PATH: &str = "default.enemies.ron";
+
+// Used until the asset is loaded, and by headless games which have no assets
+const DEFAULT_ENEMY_TABLE: &str = include_str!("../assets/default.enemies.ron");
+
+/// Everything that sets one kind of enemy apart from the others.
+///
+/// Every enemy carries the kind it was spawned as, so reloading the table
+/// only affects enemies spawned afterwards.
+#[derive(Component, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnemyKind {
+    pub name: String,
+    /// Chance of being picked, relative to the other kinds
+    pub weight: f32,
+    /// Side of the square enemy
+    pub size: f32,
+    /// Red, green and blue, from 0 to 1
+    pub color: [f32; 3],
+    /// Multiple of the base enemy speed
+    pub speed: f32,
+    /// How much faster the enemy is for every enemy destroyed, as a fraction
+    /// of its starting speed
+    pub speed_ramp: f32,
+    /// Health the enemy starts with, taking a point off for every bullet
+    pub hit_points: u32,
+    /// Points scored for destroying it
+    pub score: u32,
+    /// Name of the way it moves, one of the [`EnemyBehaviors`]
+    pub behavior: String,
+    /// How the enemy shoots back, if it does
+    #[serde(default)]
+    pub fire: Option<EnemyFire>,
+    /// What the enemy breaks into when destroyed, if anything
+    #[serde(default)]
+    pub split: Option<EnemySplit>,
+}
+
+/// How an enemy kind breaks apart into smaller, faster enemies when
+/// destroyed, which break apart in turn until they would be too small
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnemySplit {
+    /// Enemies it breaks into
+    pub children: u32,
+    /// Size of the children as a fraction of their parent's
+    pub shrink: f32,
+    /// Speed of the children as a multiple of their parent's
+    pub speedup: f32,
+    /// Smallest size children can have
+    pub min_size: f32,
+}
+
+/// A plain enemy chasing the player, going down to a single bullet for a
+/// point, to build other kinds from
+impl Default for EnemyKind {
+    fn default() -> Self {
+        Self {
+            name: "enemy".into(),
+            weight: 1.,
+            size: ENEMY_SIZE,
+            color: [1., 1., 1.],
+            speed: 1.,
+            speed_ramp: 0.,
+            hit_points: 1,
+            score: 1,
+            behavior: "chase".into(),
+            fire: None,
+            split: None,
+        }
+    }
+}
+
+impl EnemyKind {
+    pub fn color(&self) -> Color {
+        let [r, g, b] = self.color;
+        Color::rgb(r, g, b)
+    }
+
+    /// Kind of the enemies this one breaks into, if it splits at all. They
+    /// have half its hit points, rounded up, and score the same.
+    pub fn child(&self) -> Option<EnemyKind> {
+        let split = self.split.as_ref()?;
+        let size = self.size * split.shrink;
+        if size < split.min_size {
+            return None;
+        }
+        Some(EnemyKind {
+            size,
+            speed: self.speed * split.speedup,
+            hit_points: self.hit_points.div_ceil(2),
+            ..self.clone()
+        })
+    }
+}
+
+/// The kinds of enemy spawned during a game, with how often each appears
+#[derive(Asset, Resource, TypePath, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EnemyTable {
+    pub kinds: Vec<EnemyKind>,
+}
+
+#[derive(Debug)]
+pub enum EnemyTableError {
+    Io(io::Error),
+    Format(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for EnemyTableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            EnemyTableError::Io(err) => write!(f, "could not read enemy table: {err}"),
+            EnemyTableError::Format(err) => write!(f, "invalid enemy table: {err}"),
+            EnemyTableError::Invalid(reason) => write!(f, "invalid enemy table: {reason}"),
+        }
+    }
+}
+
+impl Error for EnemyTableError {}
+
+impl From<io::Error> for EnemyTableError {
+    fn from(err: io::Error) -> Self {
+        EnemyTableError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for EnemyTableError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        EnemyTableError::Format(err)
+    }
+}
+
+impl EnemyTable {
+    /// Parses a table, checking everything but the behaviors, which are
+    /// checked against the [`EnemyBehaviors`] once the table is applied
+    pub fn from_ron(ron: &str) -> Result<Self, EnemyTableError> {
+        let table: Self = ron::from_str(ron)?;
+        table.validate()?;
+        Ok(table)
+    }
+
+    fn validate(&self) -> Result<(), EnemyTableError> {
+        let invalid = |reason: String| Err(EnemyTableError::Invalid(reason));
+        for kind in &self.kinds {
+            // NaN would slip through every comparison below
+            if ![kind.weight, kind.size, kind.speed, kind.speed_ramp]
+                .iter()
+                .all(|value| value.is_finite())
+            {
+                return invalid(format!("{} has a number that isn't finite", kind.name));
+            }
+            if kind.weight < 0. {
+                return invalid(format!("{} has a negative weight", kind.name));
+            }
+            if kind.size <= 0. {
+                return invalid(format!("{} has no size", kind.name));
+            }
+            if kind.hit_points == 0 {
+                return invalid(format!("{} has no hit points", kind.name));
+            }
+            if let Some(problem) = kind.fire.as_ref().and_then(EnemyFire::problem) {
+                return invalid(format!("{} {problem}", kind.name));
+            }
+            if let Some(split) = &kind.split {
+                if ![split.shrink, split.min_size, split.speedup]
+                    .iter()
+                    .all(|value| value.is_finite())
+                {
+                    return invalid(format!(
+                        "{} splits with a number that isn't finite",
+                        kind.name
+                    ));
+                }
+                if split.children < 2 {
+                    return invalid(format!("{} splits into fewer than 2", kind.name));
+                }
+                if split.shrink <= 0. || split.shrink >= 1. || split.min_size <= 0. {
+                    return invalid(format!(
+                        "{} needs to shrink between 0 and 1 down to a positive size",
+                        kind.name
+                    ));
+                }
+                if split.speedup < 1. {
+                    return invalid(format!("{} splits into slower enemies", kind.name));
+                }
+            }
+        }
+        if self.kinds.iter().map(|kind| kind.weight).sum::<f32>() <= 0. {
+            return invalid("no kind has a positive weight".into());
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, name: &str) -> Option<&EnemyKind> {
+        self.kinds.iter().find(|kind| kind.name == name)
+    }
+
+    /// A kind chosen at random, in proportion to the weights
+    pub fn pick(&self, rng: &mut Pcg32) -> &EnemyKind {
+        let total: f32 = self.kinds.iter().map(|kind| kind.weight).sum();
+        let mut left = rng.gen::<f32>() * total;
+        for kind in &self.kinds {
+            if left < kind.weight {
+                return kind;
+            }
+            left -= kind.weight;
+        }
+        // Rounding can leave a sliver past the last weight
+        self.kinds
+            .iter()
+            .rev()
+            .find(|kind| kind.weight > 0.)
+            .unwrap()
+    }
+}
+
+impl Default for EnemyTable {
+    fn default() -> Self {
+        Self::from_ron(DEFAULT_ENEMY_TABLE).expect("the default enemy table is valid")
+    }
+}
+
+#[derive(Default)]
+pub struct EnemyTableLoader;
+
+impl AssetLoader for EnemyTableLoader {
+    type Asset = EnemyTable;
+    type Settings = ();
+    type Error = EnemyTableError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<EnemyTable, EnemyTableError>> {
+        Box::pin(async move {
+            let mut ron = String::new();
+            reader.read_to_string(&mut ron).await?;
+            EnemyTable::from_ron(&ron)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["enemies.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct EnemyTableHandle(Handle<EnemyTable>);
+
+pub fn load_enemy_table(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(EnemyTableHandle(asset_server.load(ENEMY_TABLE_PATH)));
+}
+
+/// Switches to the table from the asset once it is loaded, and whenever the
+/// file changes, unless it uses unknown behaviors
+pub fn apply_enemy_table(
+    mut asset_events: EventReader<AssetEvent<EnemyTable>>,
+    handle: Res<EnemyTableHandle>,
+    tables: Res<Assets<EnemyTable>>,
+    behaviors: Res<EnemyBehaviors>,
+    mut enemy_table: ResMut<EnemyTable>,
+) {
+    for event in asset_events.read() {
+        if event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0) {
+            if let Some(table) = tables.get(&handle.0) {
+                if let Err(err) = behaviors.check(table) {
+                    error!("Kept the previous enemy kinds: {err}");
+                    continue;
+                }
+                *enemy_table = table.clone();
+                info!("Loaded {} enemy kinds", enemy_table.kinds.len());
+            }
+        }
+    }
+}
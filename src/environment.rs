@@ -0,0 +1,112 @@
+use std::{error::Error, fmt};
+
+use bevy::{ecs::event::ManualEventReader, prelude::*};
+use serde::Serialize;
+
+use crate::{Direction, EnemyKilledEvent, Observation, PlayerDiedEvent, PlayerInput, Simulation};
+
+/// Reward for every enemy shot down
+pub const KILL_REWARD: f32 = 1.0;
+/// Reward for dying, which also ends the episode
+pub const DEATH_REWARD: f32 = -10.0;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct Step {
+    pub observation: Observation,
+    pub reward: f32,
+    pub done: bool,
+}
+
+/// An action the environment can't take
+#[derive(Clone, Debug, PartialEq)]
+pub struct InvalidAction(pub PlayerInput);
+
+impl fmt::Display for InvalidAction {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "action directions must be finite numbers, got {:?}",
+            self.0
+        )
+    }
+}
+
+impl Error for InvalidAction {}
+
+/// The game as a step-based environment for training agents.
+///
+/// Each episode is one game, starting from [`Environment::reset`] and lasting
+/// until the player dies. Every [`Environment::step`] runs a single tick with
+/// the given action.
+pub struct Environment {
+    simulation: Simulation,
+    enemy_killed_reader: ManualEventReader<EnemyKilledEvent>,
+    player_died_reader: ManualEventReader<PlayerDiedEvent>,
+    done: bool,
+}
+
+impl Environment {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            simulation: Simulation::new(seed),
+            enemy_killed_reader: default(),
+            player_died_reader: default(),
+            done: false,
+        }
+    }
+
+    pub fn reset(&mut self, seed: u64) -> Observation {
+        *self = Self::new(seed);
+        self.simulation.observe()
+    }
+
+    /// Directions longer than 1 are shortened to unit length, and actions
+    /// with non-finite directions are rejected without stepping. Once the
+    /// episode is done, further steps don't advance the game.
+    pub fn step(&mut self, action: PlayerInput) -> Result<Step, InvalidAction> {
+        let directions = [&action.movement, &action.fire];
+        if directions
+            .iter()
+            .any(|direction| !direction.x.is_finite() || !direction.y.is_finite())
+        {
+            return Err(InvalidAction(action));
+        }
+        if !self.done {
+            self.simulation.set_input(PlayerInput {
+                movement: clamp_to_unit(&action.movement),
+                fire: clamp_to_unit(&action.fire),
+            });
+            self.simulation.tick();
+        }
+
+        let world = self.simulation.world();
+        let kills = self
+            .enemy_killed_reader
+            .read(world.resource::<Events<EnemyKilledEvent>>())
+            .count();
+        let died = self
+            .player_died_reader
+            .read(world.resource::<Events<PlayerDiedEvent>>())
+            .count()
+            > 0;
+        self.done |= died;
+
+        Ok(Step {
+            observation: self.simulation.observe(),
+            reward: kills as f32 * KILL_REWARD + if died { DEATH_REWARD } else { 0.0 },
+            done: self.done,
+        })
+    }
+
+    pub fn simulation(&mut self) -> &mut Simulation {
+        &mut self.simulation
+    }
+}
+
+fn clamp_to_unit(direction: &Direction) -> Direction {
+    let clamped = Vec2::new(direction.x, direction.y).clamp_length_max(1.);
+    Direction {
+        x: clamped.x,
+        y: clamped.y,
+    }
+}
@@ -0,0 +1,99 @@
+//! Events describing what happens during a game.
+//!
+//! They are sent during the gameplay tick, in `FixedUpdate`, and can be read
+//! with an `EventReader` from any schedule of the app embedding the game.
+
+use bevy::prelude::*;
+
+use crate::{DeathCause, Direction};
+
+/// A bullet hit an enemy, taking `damage` off its health. The bullet is
+/// despawned at the end of the tick.
+#[derive(Event, Clone, Debug)]
+pub struct EnemyHitEvent {
+    pub enemy: Entity,
+    pub position: Vec2,
+    pub bullet: Entity,
+    pub damage: u32,
+}
+
+/// A bullet took an enemy's last hit point. Both are despawned at the end of
+/// the tick.
+#[derive(Event, Clone, Debug)]
+pub struct EnemyKilledEvent {
+    pub enemy: Entity,
+    pub position: Vec2,
+    pub bullet: Entity,
+    /// Points the kill added to the score
+    pub score_delta: u32,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct BulletFiredEvent {
+    pub bullet: Entity,
+    pub origin: Vec2,
+    pub direction: Direction,
+}
+
+/// Wall an enemy entered the arena from
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EnemySpawnSide {
+    Top,
+    Left,
+    Right,
+    /// A point inside the arena given by the wave script
+    Point,
+    /// Where a bigger enemy broke apart
+    Split,
+}
+
+#[derive(Event, Clone, Debug)]
+pub struct EnemySpawnedEvent {
+    pub enemy: Entity,
+    /// Name of the enemy's kind
+    pub kind: String,
+    pub side: EnemySpawnSide,
+    pub position: Vec2,
+}
+
+/// The player was hit, ending the game
+#[derive(Event, Clone, Debug)]
+pub struct PlayerDiedEvent {
+    /// Enemy that touched the player, or enemy bullet that hit it
+    pub killer: Entity,
+    pub cause: DeathCause,
+    pub position: Vec2,
+    /// Seconds of game time the player survived, including this tick
+    pub survival_time: f32,
+}
+
+/// The break before a wave is over, and its enemies start coming in
+#[derive(Event, Clone, Debug)]
+pub struct WaveStartedEvent {
+    /// Counting from 1
+    pub wave: u32,
+}
+
+/// A bullet finished off a boss, also reported as an [`EnemyKilledEvent`]
+#[derive(Event, Clone, Debug)]
+pub struct BossDefeatedEvent {
+    pub boss: Entity,
+    pub name: String,
+    pub position: Vec2,
+    /// Points the boss added to the score
+    pub score_delta: u32,
+}
+
+/// The score went up, or was reset for a new game
+#[derive(Event, Clone, Debug)]
+pub struct ScoreChangedEvent {
+    pub previous: u32,
+    pub score: u32,
+}
+
+/// Bullets wore down a piece of cover, which is despawned at the end of the tick
+#[derive(Event, Clone, Debug)]
+pub struct CoverDestroyedEvent {
+    pub cover: Entity,
+    pub position: Vec2,
+}
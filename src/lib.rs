@@ -1,30 +1,124 @@
+use std::f32::consts::TAU;
+
 use bevy::{
     app::App,
+    input::{InputPlugin, InputSystem},
     prelude::*,
-    sprite::{collide_aabb::collide, MaterialMesh2dBundle},
+    render::view::VisibilitySystems,
+    transform::TransformSystem,
+    utils::HashSet,
 };
-use rand::{rngs::SmallRng, Rng as _, SeedableRng};
+use rand::Rng as _;
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
 
+mod arena;
+mod behavior;
+mod boss;
+mod bot;
+mod collision;
+mod enemy_fire;
+mod enemy_kind;
+mod environment;
+mod events;
+mod navigation;
+mod observation;
+mod replay;
+mod rng;
+mod separation;
+mod simulation;
+mod snapshot;
+mod spatial_hash;
+mod stats;
+mod visuals;
 mod wall_scoreboard;
-use wall_scoreboard::{setup_score_board, setup_walls, update_scoreboard};
+mod waves;
+use arena::fade_damaged_cover;
+pub use arena::{ArenaLayout, Cover, Obstacle, COVER_HIT_POINTS, OBSTACLE_THICKNESS, PILLAR_SIZE};
+pub use behavior::{
+    Behavior, BehaviorState, Chase, Dash, EnemyBehaviors, Flee, Intercept, Orbit, Steering, Wander,
+    Zigzag,
+};
+use boss::{advance_boss_phases, setup_boss_health_bar, update_boss_health_bar};
+pub use boss::{Boss, BossKind, BossPhase};
+use bot::drive_bot;
+pub use bot::{bot_by_name, ActiveBot, Bot, CornerBot, KiteBot, RandomBot, BOT_NAMES};
+pub use collision::CollisionShape;
+use collision::{clear_path, move_against_walls};
+use enemy_fire::{cancel_bullets, enemy_bullet_bundle, fire_enemy_bullets};
+pub use enemy_fire::{BulletCancel, EnemyFire, EnemyGun, FirePattern, ENEMY_BULLET_SIZE};
+use enemy_kind::{apply_enemy_table, load_enemy_table};
+pub use enemy_kind::{
+    EnemyKind, EnemySplit, EnemyTable, EnemyTableError, EnemyTableLoader, ENEMY_TABLE_PATH,
+};
+pub use environment::{Environment, InvalidAction, Step, DEATH_REWARD, KILL_REWARD};
+pub use events::{
+    BossDefeatedEvent, BulletFiredEvent, CoverDestroyedEvent, EnemyHitEvent, EnemyKilledEvent,
+    EnemySpawnSide, EnemySpawnedEvent, PlayerDiedEvent, ScoreChangedEvent, WaveStartedEvent,
+};
+use navigation::update_flow_field;
+pub use navigation::{FlowField, NAVIGATION_CELL_SIZE};
+pub use observation::{EnemyObservation, Observation};
+pub use replay::{
+    RecordPlugin, Replay, ReplayError, ReplayOutcome, ReplayPlugin, TickCheck, REPLAY_VERSION,
+};
+use rng::restart_rng;
+pub use rng::{GameRng, RngStream};
+pub use separation::EnemySeparation;
+pub use simulation::Simulation;
+pub use snapshot::{
+    BulletSnapshot, CoverSnapshot, EnemyBulletSnapshot, EnemySnapshot, RestorePlugin, Snapshot,
+    SnapshotError, SnapshotPlugin, SNAPSHOT_VERSION,
+};
+pub use spatial_hash::COLLISION_CELL_SIZE;
+use spatial_hash::{index_enemies, EnemySpatialHash};
+use stats::{reset_run_stats, track_run_stats};
+pub use stats::{Death, DeathCause, RunStats};
+use visuals::{
+    attach_meshes, end_hit_flashes, interpolate_translations, place_health_bars, setup_meshes,
+    show_enemy_damage, store_previous_translations,
+};
+pub use wall_scoreboard::ArenaEdge;
+use wall_scoreboard::{
+    setup_score_board, setup_walls, spawn_cover, update_arena_bounds, update_scoreboard, Walls,
+};
+use waves::{apply_wave_script, load_wave_script, setup_wave_banner, update_wave_banner, Waves};
+pub use waves::{
+    EndlessWaves, PendingSpawn, SpawnFrom, SpawnGroup, Wave, WaveDirector, WaveScript,
+    WaveScriptError, WaveScriptLoader, WaveStage, WAVE_SCRIPT_PATH,
+};
 
 pub const PLAYER_SIZE: f32 = 30.0;
+pub const PLAYER_SPEED: f32 = 600.0;
 pub const JOYSTICK_SIZE: f32 = 250.0;
 pub const JOYSTICK_ZONE_SIZE: f32 = 180.0;
 pub const ENEMY_SIZE: f32 = 20.0;
 pub const BULLET_SIZE: f32 = 5.0;
+pub const BULLET_DAMAGE: u32 = 1;
+pub const BULLET_SPEED: f32 = 600.0;
 pub const WALL_THICKNESS: f32 = 10.0;
+/// Seconds the enemies an enemy breaks into fly apart before going after the
+/// player
+pub const SPLIT_SCATTER_SECONDS: f32 = 0.4;
 
 #[cfg(feature = "mobile")]
 pub const INITIAL_ENEMY_SPEED_FACTOR: f32 = 40.;
 #[cfg(not(feature = "mobile"))]
 pub const INITIAL_ENEMY_SPEED_FACTOR: f32 = 70.;
 
+#[cfg(feature = "mobile")]
+pub const ENEMY_BULLET_SPEED: f32 = 150.;
+#[cfg(not(feature = "mobile"))]
+pub const ENEMY_BULLET_SPEED: f32 = 250.;
+
 #[cfg(feature = "mobile")]
 pub const MAX_NUMBER_OF_ENEMIES: usize = 4;
 #[cfg(not(feature = "mobile"))]
 pub const MAX_NUMBER_OF_ENEMIES: usize = 8;
 
+pub const MOBILE_WINDOW_WIDTH: f32 = 900.;
+pub const MOBILE_WINDOW_HEIGHT: f32 = 1500.;
+
 pub const LEFT_WALL: f32 = -350.;
 pub const RIGHT_WALL: f32 = 350.;
 
@@ -42,31 +136,50 @@ pub const TRUE_BOTTOM_WALL: f32 = -300.;
 #[cfg(not(feature = "mobile"))]
 pub const PRE_BOTTOM_WALL: f32 = TRUE_BOTTOM_WALL;
 
-pub const ENEMY_LEFT_BOUNDARY: f32 = LEFT_WALL + WALL_THICKNESS + ENEMY_SIZE / 2.;
-pub const ENEMY_RIGHT_BOUNDARY: f32 = RIGHT_WALL - WALL_THICKNESS - ENEMY_SIZE / 2.;
-pub const ENEMY_BOTTOM_BOUNDARY: f32 = PRE_BOTTOM_WALL + WALL_THICKNESS + ENEMY_SIZE / 2.;
-pub const ENEMY_TOP_BOUNDARY: f32 = TOP_WALL - WALL_THICKNESS - ENEMY_SIZE / 2.;
-
-pub const PLAYER_LEFT_BOUNDARY: f32 = LEFT_WALL + WALL_THICKNESS + PLAYER_SIZE / 2.;
-pub const PLAYER_RIGHT_BOUNDARY: f32 = RIGHT_WALL - WALL_THICKNESS - PLAYER_SIZE / 2.;
-pub const PLAYER_BOTTOM_BOUNDARY: f32 = PRE_BOTTOM_WALL + WALL_THICKNESS + PLAYER_SIZE / 2.;
-pub const PLAYER_TOP_BOUNDARY: f32 = TOP_WALL - WALL_THICKNESS - PLAYER_SIZE / 2.;
-
-pub const BULLET_LEFT_BOUNDARY: f32 = LEFT_WALL + WALL_THICKNESS + BULLET_SIZE / 2.;
-pub const BULLET_RIGHT_BOUNDARY: f32 = RIGHT_WALL - WALL_THICKNESS - BULLET_SIZE / 2.;
-pub const BULLET_BOTTOM_BOUNDARY: f32 = PRE_BOTTOM_WALL + WALL_THICKNESS + BULLET_SIZE / 2.;
-pub const BULLET_TOP_BOUNDARY: f32 = TOP_WALL - WALL_THICKNESS - BULLET_SIZE / 2.;
+// Where the player stops against the walls of the arena
+pub const PLAYER_LEFT_BOUNDARY: f32 = LEFT_WALL + WALL_THICKNESS / 2. + PLAYER_SIZE / 2.;
+pub const PLAYER_RIGHT_BOUNDARY: f32 = RIGHT_WALL - WALL_THICKNESS / 2. - PLAYER_SIZE / 2.;
+pub const PLAYER_BOTTOM_BOUNDARY: f32 = PRE_BOTTOM_WALL + WALL_THICKNESS / 2. + PLAYER_SIZE / 2.;
+pub const PLAYER_TOP_BOUNDARY: f32 = TOP_WALL - WALL_THICKNESS / 2. - PLAYER_SIZE / 2.;
 
 pub const WALL_COLOR: Color = Color::rgb(0.8, 0.8, 0.8);
+pub const COVER_COLOR: Color = Color::rgb(0.4, 0.7, 0.4);
 
 pub const LEFT_JOYSTICK_X: f32 = LEFT_WALL * 0.8 + RIGHT_WALL * 0.2;
 pub const RIGHT_JOYSTICK_X: f32 = LEFT_WALL * 0.2 + RIGHT_WALL * 0.8;
 pub const JOYSTICK_Y: f32 = (TRUE_BOTTOM_WALL + PRE_BOTTOM_WALL) / 2.0;
 
-#[derive(Component, Default, Clone)]
-struct Direction {
-    x: f32,
-    y: f32,
+#[derive(Component, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Direction {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// Directions the player wants to move and fire in during the current tick.
+///
+/// Filled from the keyboard or the touch joysticks, unless the [`InputSource`]
+/// says otherwise.
+#[derive(Resource, Default, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PlayerInput {
+    pub movement: Direction,
+    pub fire: Direction,
+}
+
+/// Systems that fill [`PlayerInput`] for the current tick, in place of the
+/// live input read once per frame
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct InputSet;
+
+/// Where [`PlayerInput`] comes from
+#[derive(Resource, Default, Debug, PartialEq, Eq)]
+pub enum InputSource {
+    #[default]
+    Live,
+    Replay,
+    /// Set directly by code embedding the game, e.g. tests
+    External,
+    /// Decided by the [`ActiveBot`]
+    Bot,
 }
 
 #[derive(Component)]
@@ -84,33 +197,88 @@ struct Enemy;
 #[derive(Component)]
 struct Bullet;
 
-struct Rng(SmallRng);
-impl Default for Rng {
-    fn default() -> Self {
-        Self(SmallRng::from_entropy())
+/// Bullet fired by an enemy, which only hits the player
+#[derive(Component)]
+struct EnemyBullet;
+
+/// How much damage an enemy can still take before it is destroyed
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Health {
+    pub current: u32,
+    pub max: u32,
+}
+
+impl Health {
+    pub fn new(max: u32) -> Self {
+        Self { current: max, max }
+    }
+
+    /// Fraction of the maximum left, from 0 to 1
+    pub fn fraction(&self) -> f32 {
+        self.current as f32 / self.max.max(1) as f32
     }
 }
 
+/// Health a bullet takes away from what it hits
+#[derive(Component, Clone, Copy, Debug, PartialEq)]
+pub struct Damage(pub u32);
+
+/// Seconds left for an enemy that just broke off a bigger one to keep flying
+/// the way it was sent
+#[derive(Component)]
+struct Scatter(f32);
+
+/// Enemies split off during the current tick, which only join the enemy
+/// queries once it ends
+#[derive(Resource, Default)]
+struct SplitChildren(usize);
+
+/// Wall hits a bullet has left before it is destroyed instead of bouncing
+#[derive(Component)]
+struct RemainingBounces(u32);
+
+/// Where a bullet was before its last move, so that collisions are checked
+/// along the whole path it flew rather than only where it ended up
+#[derive(Component)]
+struct PreviousPosition(Vec2);
+
+// Playback speed of the kill sound when a boss is defeated
+const BOSS_DEFEATED_SOUND_SPEED: f32 = 0.5;
+
 #[derive(Resource)]
 struct Sounds {
     collision_bullet_enemy: Handle<AudioSource>,
     game_over: Handle<AudioSource>,
 }
 
-#[derive(Default, Event)]
-struct CollisionBulletEnemyEvent;
-
-#[derive(Default, Event)]
-struct GameOverEvent;
+/// Number of enemies destroyed so far, which enemies speed up with
+#[derive(Resource, Default)]
+pub struct DestroyedEnemyCount(pub u32);
 
+/// The score, the points of every enemy destroyed so far
 #[derive(Resource, Default)]
-pub struct DestroyedEnemyCount(u32);
+pub struct Score(pub u32);
+
+/// Inside of the arena, between its walls
+#[derive(Resource, Default, Clone, Debug, PartialEq)]
+pub struct ArenaBounds {
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+/// How many times bullets bounce off walls before they are destroyed, none
+/// by default
+#[derive(Resource, Default, Clone, Debug, PartialEq)]
+pub struct BulletRicochet {
+    pub bounces: u32,
+}
 
-#[derive(Default)]
-struct TimeSince(f32);
+/// Seconds the game has been over for, counting towards the automatic restart
+#[derive(Resource, Default, Clone, Debug, PartialEq)]
+pub(crate) struct RestartTimer(pub(crate) f32);
 
-#[derive(States, Debug, Hash, PartialEq, Eq, Clone, Default)]
-enum AppState {
+#[derive(States, Debug, Hash, PartialEq, Eq, Clone, Default, Serialize, Deserialize)]
+pub enum AppState {
     #[default]
     GameStart,
     InGame,
@@ -118,65 +286,241 @@ enum AppState {
     GameOver,
 }
 
-pub struct GamePlugin;
+/// Gameplay ticks per second used unless configured otherwise
+pub const DEFAULT_TICK_RATE: f64 = 60.0;
+
+/// Adds the game to an app.
+///
+/// With `headless` set, only the gameplay systems are added: no camera, meshes,
+/// sounds or scoreboard are created, so the game can run under `MinimalPlugins`
+/// on machines without a window or GPU.
+///
+/// With a `seed`, every game plays out the same for the same inputs.
+///
+/// Gameplay advances in fixed ticks of `1 / tick_rate` seconds, independently
+/// of the frame rate, and rendered positions are interpolated between ticks.
+/// The tick rate must be a finite number above 0.
+pub struct GamePlugin {
+    pub headless: bool,
+    pub seed: Option<u64>,
+    pub tick_rate: f64,
+}
+
+impl Default for GamePlugin {
+    fn default() -> Self {
+        Self {
+            headless: false,
+            seed: None,
+            tick_rate: DEFAULT_TICK_RATE,
+        }
+    }
+}
+
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
+        assert!(
+            self.tick_rate.is_finite() && self.tick_rate > 0.,
+            "GamePlugin::tick_rate must be a finite number of ticks per second above 0, got {}",
+            self.tick_rate
+        );
+        if self.headless {
+            // Not part of `MinimalPlugins`, but needed to feed keys and touches
+            // to the game
+            if !app.is_plugin_added::<InputPlugin>() {
+                app.add_plugins(InputPlugin);
+            }
+        } else {
+            app.add_systems(Startup, setup)
+                .add_systems(Startup, setup_meshes)
+                .add_systems(Startup, setup_score_board)
+                .add_systems(
+                    Update,
+                    play_bullet_collide_enemy_sound.run_if(in_state(AppState::InGame)),
+                )
+                .add_systems(Update, play_game_over_sound)
+                .add_systems(Update, play_boss_defeated_sound)
+                .add_systems(Update, update_scoreboard.run_if(in_state(AppState::InGame)))
+                .add_systems(Update, fade_damaged_cover)
+                .add_systems(Update, (show_enemy_damage, end_hit_flashes).chain())
+                .init_asset::<EnemyTable>()
+                .register_asset_loader(EnemyTableLoader)
+                .add_systems(Startup, load_enemy_table)
+                .add_systems(Update, apply_enemy_table)
+                .init_asset::<WaveScript>()
+                .register_asset_loader(WaveScriptLoader)
+                .add_systems(Startup, (load_wave_script, setup_wave_banner))
+                .add_systems(Update, (apply_wave_script, update_wave_banner))
+                .add_systems(Startup, setup_boss_health_bar)
+                .add_systems(Update, update_boss_health_bar)
+                .add_systems(
+                    FixedUpdate,
+                    store_previous_translations.before(check_player_collide_enemy),
+                )
+                .add_systems(PostUpdate, attach_meshes)
+                .add_systems(
+                    PostUpdate,
+                    interpolate_translations
+                        .after(TransformSystem::TransformPropagate)
+                        .before(VisibilitySystems::CheckVisibility),
+                )
+                .add_systems(
+                    PostUpdate,
+                    place_health_bars
+                        .after(interpolate_translations)
+                        .before(VisibilitySystems::CheckVisibility),
+                );
+        }
+
         app.add_state::<AppState>()
-            .add_event::<CollisionBulletEnemyEvent>()
-            .add_event::<GameOverEvent>()
-            .add_systems(Startup, setup)
+            .insert_resource(Time::<Fixed>::from_hz(self.tick_rate))
+            .insert_resource(GameRng::new(self.seed))
+            .init_resource::<PlayerInput>()
+            .init_resource::<InputSource>()
+            .init_resource::<RestartTimer>()
+            .init_resource::<SplitChildren>()
+            .add_event::<EnemyHitEvent>()
+            .add_event::<EnemyKilledEvent>()
+            .add_event::<BulletFiredEvent>()
+            .add_event::<EnemySpawnedEvent>()
+            .add_event::<PlayerDiedEvent>()
+            .add_event::<ScoreChangedEvent>()
+            .add_event::<CoverDestroyedEvent>()
+            .add_event::<WaveStartedEvent>()
+            .add_event::<BossDefeatedEvent>()
             .add_systems(Startup, setup_walls)
-            .add_systems(Startup, setup_score_board)
+            .add_systems(PostStartup, update_arena_bounds)
             .add_systems(Startup, spawn_things)
-            .add_systems(Update, spawn_player.run_if(in_state(AppState::GameStart)))
+            .add_systems(OnEnter(AppState::GameStart), restart_rng)
+            .add_systems(OnEnter(AppState::GameStart), spawn_cover)
+            .add_systems(OnEnter(AppState::GameStart), reset_run_stats)
+            .add_systems(
+                PreUpdate,
+                read_player_input
+                    .after(InputSystem)
+                    .run_if(resource_equals(InputSource::Live)),
+            )
+            // Several ticks may run in one frame, so state changes are applied
+            // at the start of each tick rather than once per frame
             .add_systems(
-                Update,
+                FixedUpdate,
+                apply_state_transition::<AppState>
+                    .before(spawn_player)
+                    .before(InputSet)
+                    .before(game_restarter),
+            )
+            .add_systems(
+                FixedUpdate,
+                spawn_player.run_if(in_state(AppState::GameStart)),
+            )
+            .configure_sets(FixedUpdate, InputSet.before(check_player_collide_enemy))
+            .add_systems(FixedUpdate, update_arena_bounds.before(InputSet))
+            .add_systems(
+                FixedUpdate,
+                drive_bot
+                    .in_set(InputSet)
+                    .run_if(resource_equals(InputSource::Bot))
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
+                index_enemies
+                    .after(InputSet)
+                    .before(check_player_collide_enemy)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
                 check_player_collide_enemy
                     .before(shoot_bullet)
                     .run_if(in_state(AppState::InGame)),
             )
             .add_systems(
-                Update,
+                FixedUpdate,
                 shoot_bullet
                     .before(move_bullet)
                     .run_if(in_state(AppState::InGame)),
             )
             .add_systems(
-                Update,
+                FixedUpdate,
                 move_bullet
                     .before(move_player)
                     .run_if(in_state(AppState::InGame)),
             )
             .add_systems(
-                Update,
+                FixedUpdate,
+                cancel_bullets
+                    .after(move_bullet)
+                    .before(move_player)
+                    .run_if(|bullet_cancel: Res<BulletCancel>| bullet_cancel.enabled)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
                 move_player
+                    .before(check_player_collide_enemy_bullet)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
+                check_player_collide_enemy_bullet
                     .before(check_bullet_collide_enemy)
                     .run_if(in_state(AppState::InGame)),
             )
             .add_systems(
-                Update,
+                FixedUpdate,
                 check_bullet_collide_enemy
                     .before(spawn_and_move_enemies)
                     .run_if(in_state(AppState::InGame)),
             )
             .add_systems(
-                Update,
-                play_bullet_collide_enemy_sound
-                    .after(check_bullet_collide_enemy)
+                FixedUpdate,
+                update_flow_field
+                    .after(move_player)
+                    .before(spawn_and_move_enemies)
                     .run_if(in_state(AppState::InGame)),
             )
             .add_systems(
-                Update,
-                play_game_over_sound.after(check_player_collide_enemy),
+                FixedUpdate,
+                advance_boss_phases
+                    .after(check_bullet_collide_enemy)
+                    .before(spawn_and_move_enemies)
+                    .run_if(in_state(AppState::InGame)),
             )
             .add_systems(
-                Update,
+                FixedUpdate,
                 spawn_and_move_enemies.run_if(in_state(AppState::InGame)),
             )
-            .add_systems(Update, update_scoreboard.run_if(in_state(AppState::InGame)))
-            .add_systems(Update, game_restarter.run_if(in_state(AppState::GameOver)))
+            .add_systems(
+                FixedUpdate,
+                fire_enemy_bullets
+                    .after(spawn_and_move_enemies)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
+                track_run_stats
+                    .after(spawn_and_move_enemies)
+                    .run_if(in_state(AppState::InGame)),
+            )
+            .add_systems(
+                FixedUpdate,
+                game_restarter.run_if(in_state(AppState::GameOver)),
+            )
             // .add_systems(Update, bevy::window::close_on_esc)
-            .init_resource::<DestroyedEnemyCount>();
+            .init_resource::<DestroyedEnemyCount>()
+            .init_resource::<Score>()
+            .init_resource::<ArenaBounds>()
+            .init_resource::<WaveScript>()
+            .init_resource::<WaveDirector>()
+            .init_resource::<EnemySpatialHash>()
+            .init_resource::<EnemySeparation>()
+            .init_resource::<BulletRicochet>()
+            .init_resource::<BulletCancel>()
+            .init_resource::<ArenaLayout>()
+            .init_resource::<FlowField>()
+            .init_resource::<EnemyTable>()
+            .init_resource::<EnemyBehaviors>()
+            .init_resource::<RunStats>();
     }
 }
 
@@ -184,198 +528,371 @@ fn spawn_things(mut commands: Commands) {
     commands.spawn((Direction::default(),));
 }
 
-fn move_player(
+fn read_player_input(
     #[cfg(not(feature = "mobile"))] keyboard_input: Res<Input<KeyCode>>,
-    mut query: Query<&mut Transform, With<Player>>,
-    #[cfg(feature = "mobile")] camera: Query<&Transform, (With<Camera>, Without<Player>)>,
+    #[cfg(feature = "mobile")] camera: Query<&Transform, With<Camera>>,
     #[cfg(feature = "mobile")] window: Query<&Window>,
-    time: Res<Time>,
     #[cfg(feature = "mobile")] touches: Res<Touches>,
+    mut player_input: ResMut<PlayerInput>,
 ) {
-    let mut player_transform = query.single_mut();
-
-    let mut direction_x = 0.0;
-    let mut direction_y = 0.0;
+    let mut movement = Direction::default();
+    let mut fire = Direction::default();
 
     #[cfg(feature = "mobile")]
     {
-        let camera = camera.single();
-        let window = window.single();
+        // Without a window, as in the headless simulation, touches are mapped as
+        // if made on a default sized window seen through a camera at the origin
+        let camera = camera.get_single().copied().unwrap_or_default();
+        let (width, height) = window
+            .get_single()
+            .map_or((MOBILE_WINDOW_WIDTH, MOBILE_WINDOW_HEIGHT), |window| {
+                (window.width(), window.height())
+            });
         for finger in touches.iter() {
             let center = camera.translation.truncate();
-            let half_width = (window.width() / 2.0) * camera.scale.x;
-            let half_height = (window.height() / 2.0) * camera.scale.y;
+            let half_width = (width / 2.0) * camera.scale.x;
+            let half_height = (height / 2.0) * camera.scale.y;
             let left = center.x - half_width;
             let bottom = center.y - half_height;
 
             let x = left + finger.position().x * camera.scale.x;
             let y = -bottom - finger.position().y * camera.scale.y;
+
             let mut direction = Direction {
                 x: (x - RIGHT_JOYSTICK_X),
                 y: (y - JOYSTICK_Y),
             };
-            if direction.x.abs() > JOYSTICK_ZONE_SIZE || direction.y.abs() > JOYSTICK_ZONE_SIZE {
-                continue;
+            if direction.x.abs() <= JOYSTICK_ZONE_SIZE && direction.y.abs() <= JOYSTICK_ZONE_SIZE {
+                normalize_direction(&mut direction);
+                movement = direction;
+            }
+
+            let mut new_direction = Direction {
+                x: (x - LEFT_JOYSTICK_X),
+                y: (y - JOYSTICK_Y),
+            };
+            if new_direction.x.abs() <= JOYSTICK_ZONE_SIZE
+                && new_direction.y.abs() <= JOYSTICK_ZONE_SIZE
+            {
+                normalize_direction(&mut new_direction);
+                fire = new_direction;
             }
-            normalize_direction(&mut direction);
-            direction_x = direction.x;
-            direction_y = direction.y;
         }
     }
     #[cfg(not(feature = "mobile"))]
     {
         if keyboard_input.pressed(KeyCode::Left) {
-            direction_x -= 1.0;
+            movement.x -= 1.0;
         }
 
         if keyboard_input.pressed(KeyCode::Right) {
-            direction_x += 1.0;
+            movement.x += 1.0;
         }
 
         if keyboard_input.pressed(KeyCode::Up) {
-            direction_y += 1.0;
+            movement.y += 1.0;
         }
 
         if keyboard_input.pressed(KeyCode::Down) {
-            direction_y -= 1.0;
+            movement.y -= 1.0;
+        }
+
+        if keyboard_input.pressed(KeyCode::F) {
+            fire.y = 1.0;
+        }
+        if keyboard_input.pressed(KeyCode::D) {
+            fire.x = -1.0;
+        }
+        if keyboard_input.pressed(KeyCode::G) {
+            fire.x = 1.0;
         }
     }
 
-    let new_player_position =
-        player_transform.translation.x + direction_x * 600. * time.delta_seconds();
-    player_transform.translation.x =
-        new_player_position.clamp(PLAYER_LEFT_BOUNDARY, PLAYER_RIGHT_BOUNDARY);
+    player_input.movement = movement;
+    player_input.fire = fire;
+}
 
-    let new_player_position =
-        player_transform.translation.y + direction_y * 600. * time.delta_seconds();
-    player_transform.translation.y =
-        new_player_position.clamp(PLAYER_BOTTOM_BOUNDARY, PLAYER_TOP_BOUNDARY);
+fn move_player(
+    mut query: Query<(&mut Transform, &CollisionShape), With<Player>>,
+    player_input: Res<PlayerInput>,
+    walls: Walls,
+    time: Res<Time>,
+) {
+    let (mut player_transform, player_shape) = query.single_mut();
+    let direction = &player_input.movement;
+
+    let movement = Vec2::new(direction.x, direction.y) * PLAYER_SPEED * time.delta_seconds();
+    let (position, _) = move_against_walls(
+        player_shape,
+        player_transform.translation.truncate(),
+        movement,
+        &walls.boxes(),
+        false,
+    );
+    player_transform.translation = position.extend(player_transform.translation.z);
 }
 
 fn shoot_bullet(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    #[cfg(not(feature = "mobile"))] keyboard_input: Res<Input<KeyCode>>,
     query: Query<&Transform, With<Player>>,
-    #[cfg(feature = "mobile")] camera: Query<&Transform, (With<Camera>, Without<Player>)>,
-    #[cfg(feature = "mobile")] window: Query<&Window>,
-    #[cfg(feature = "mobile")] touches: Res<Touches>,
+    player_input: Res<PlayerInput>,
+    bullet_ricochet: Res<BulletRicochet>,
+    mut bullet_fired_event: EventWriter<BulletFiredEvent>,
 ) {
     let player_transform = query.single();
-    let mut direction = Direction::default();
-
-    #[cfg(feature = "mobile")]
-    {
-        let camera = camera.single();
-        let window = window.single();
-        for finger in touches.iter() {
-            let center = camera.translation.truncate();
-            let half_width = (window.width() / 2.0) * camera.scale.x;
-            let half_height = (window.height() / 2.0) * camera.scale.y;
-            let left = center.x - half_width;
-            let bottom = center.y - half_height;
-
-            let x = left + finger.position().x * camera.scale.x;
-            let y = -bottom - finger.position().y * camera.scale.y;
-            let mut new_direction = Direction {
-                x: (x - LEFT_JOYSTICK_X),
-                y: (y - JOYSTICK_Y),
-            };
-            if new_direction.x.abs() > JOYSTICK_ZONE_SIZE || direction.y.abs() > JOYSTICK_ZONE_SIZE
-            {
-                continue;
-            }
-            normalize_direction(&mut new_direction);
-            direction = new_direction;
-        }
-    }
-    #[cfg(not(feature = "mobile"))]
-    {
-        if keyboard_input.pressed(KeyCode::F) {
-            direction.y = 1.0;
-        }
-        if keyboard_input.pressed(KeyCode::D) {
-            direction.x = -1.0;
-        }
-        if keyboard_input.pressed(KeyCode::G) {
-            direction.x = 1.0;
-        }
-    }
+    let direction = &player_input.fire;
 
     if direction.x == 0.0 && direction.y == 0.0 {
         return;
     }
-    commands.spawn((
-        direction.clone(),
-        MaterialMesh2dBundle {
-            mesh: meshes.add(Mesh::from(shape::Quad::default())).into(),
-            transform: Transform::from_xyz(
-                player_transform.translation.x + direction.x * PLAYER_SIZE / 2.,
-                player_transform.translation.y + direction.y * PLAYER_SIZE / 2.,
-                player_transform.translation.z,
-            )
-            .with_scale(Vec3::splat(BULLET_SIZE)),
-            material: materials.add(ColorMaterial::from(Color::RED)),
-            ..default()
-        },
-        Bullet,
-    ));
+    let origin = Vec3::new(
+        player_transform.translation.x + direction.x * PLAYER_SIZE / 2.,
+        player_transform.translation.y + direction.y * PLAYER_SIZE / 2.,
+        player_transform.translation.z,
+    );
+    let bullet = commands
+        .spawn(bullet_bundle(
+            origin,
+            direction.clone(),
+            bullet_ricochet.bounces,
+        ))
+        .id();
+    bullet_fired_event.send(BulletFiredEvent {
+        bullet,
+        origin: origin.truncate(),
+        direction: direction.clone(),
+    });
 }
 
+type MovingBullet = (
+    Entity,
+    &'static mut Transform,
+    &'static mut PreviousPosition,
+    &'static mut Direction,
+    &'static mut RemainingBounces,
+    &'static CollisionShape,
+    Has<EnemyBullet>,
+);
+
+type AnyBullet = Or<(With<Bullet>, With<EnemyBullet>)>;
+
+// Moves the enemies' bullets as well as the player's
 fn move_bullet(
     mut commands: Commands,
-    mut query: Query<(Entity, &mut Transform, &Direction), With<Bullet>>,
+    mut query: Query<MovingBullet, AnyBullet>,
+    walls: Walls,
+    mut query_cover: Query<&mut Cover>,
+    arena_bounds: Res<ArenaBounds>,
     time: Res<Time>,
+    mut cover_destroyed_event: EventWriter<CoverDestroyedEvent>,
 ) {
-    for (entity, mut transform, direction) in query.iter_mut() {
-        if transform.translation.y > BULLET_TOP_BOUNDARY
-            || transform.translation.y < BULLET_BOTTOM_BOUNDARY
-            || transform.translation.x > BULLET_RIGHT_BOUNDARY
-            || transform.translation.x < BULLET_LEFT_BOUNDARY
-        {
+    let (wall_entities, walls): (Vec<_>, Vec<_>) = walls.boxes_by_entity().into_iter().unzip();
+    for (
+        entity,
+        mut transform,
+        mut previous_position,
+        mut direction,
+        mut bounces,
+        shape,
+        enemy_bullet,
+    ) in query.iter_mut()
+    {
+        let position = transform.translation.truncate();
+        previous_position.0 = position;
+        // Walls stop bullets, this only catches those that somehow got past
+        if position.cmplt(arena_bounds.min).any() || position.cmpgt(arena_bounds.max).any() {
             commands.entity(entity).despawn();
+            continue;
+        }
+
+        let speed = if enemy_bullet {
+            ENEMY_BULLET_SPEED
         } else {
-            transform.translation.x += direction.x * 600. * time.delta_seconds();
-            transform.translation.y += direction.y * 600. * time.delta_seconds();
+            BULLET_SPEED
+        };
+        let movement = Vec2::new(direction.x, direction.y) * speed * time.delta_seconds();
+        let (position, wall_hit) = move_against_walls(shape, position, movement, &walls, true);
+        transform.translation = position.extend(transform.translation.z);
+        if let Some(hit) = wall_hit {
+            let wall = wall_entities[hit.wall];
+            if let Ok(mut cover) = query_cover.get_mut(wall) {
+                // Cover soaks up bullets rather than bouncing them
+                commands.entity(entity).despawn();
+                if cover.hit() {
+                    commands.entity(wall).despawn();
+                    cover_destroyed_event.send(CoverDestroyedEvent {
+                        cover: wall,
+                        position: walls[hit.wall].0,
+                    });
+                }
+                continue;
+            }
+            let normal = hit.normal;
+            if bounces.0 == 0 {
+                commands.entity(entity).despawn();
+            } else {
+                bounces.0 -= 1;
+                let heading = Vec2::new(direction.x, direction.y);
+                let reflected = heading - 2. * heading.dot(normal) * normal;
+                direction.x = reflected.x;
+                direction.y = reflected.y;
+            }
         }
     }
 }
 
+fn bullet_bundle(
+    translation: Vec3,
+    direction: Direction,
+    bounces: u32,
+) -> (
+    Direction,
+    SpatialBundle,
+    CollisionShape,
+    RemainingBounces,
+    PreviousPosition,
+    Damage,
+    Bullet,
+) {
+    (
+        direction,
+        SpatialBundle::from_transform(
+            Transform::from_translation(translation).with_scale(Vec3::splat(BULLET_SIZE)),
+        ),
+        CollisionShape::Box {
+            half_size: Vec2::splat(BULLET_SIZE / 2.),
+        },
+        RemainingBounces(bounces),
+        PreviousPosition(translation.truncate()),
+        Damage(BULLET_DAMAGE),
+        Bullet,
+    )
+}
+
+type HittingBullet = (
+    Entity,
+    &'static Transform,
+    &'static PreviousPosition,
+    &'static CollisionShape,
+    &'static Damage,
+    &'static Direction,
+);
+
+#[allow(clippy::too_many_arguments)]
 fn check_bullet_collide_enemy(
     mut commands: Commands,
-    bullet_query: Query<(Entity, &Transform), With<Bullet>>,
-    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+    bullet_query: Query<HittingBullet, With<Bullet>>,
+    mut enemy_query: Query<(&mut Health, &EnemyKind, Has<Boss>), With<Enemy>>,
+    enemy_spatial_hash: Res<EnemySpatialHash>,
     mut destroyed_enemy_count: ResMut<DestroyedEnemyCount>,
-    mut collision_bullet_enemy_event: EventWriter<CollisionBulletEnemyEvent>,
+    mut score: ResMut<Score>,
+    mut enemy_hit_event: EventWriter<EnemyHitEvent>,
+    mut enemy_killed_event: EventWriter<EnemyKilledEvent>,
+    mut boss_defeated_event: EventWriter<BossDefeatedEvent>,
+    mut score_changed_event: EventWriter<ScoreChangedEvent>,
+    mut enemy_spawned_event: EventWriter<EnemySpawnedEvent>,
+    mut split_children: ResMut<SplitChildren>,
 ) {
-    for (enemy_entity, enemy_transform) in enemy_query.iter() {
-        for (bullet_entity, bullet_transform) in bullet_query.iter() {
-            let collision_bullet_enemy = collide(
-                bullet_transform.translation,
-                bullet_transform.scale.truncate(),
-                enemy_transform.translation,
-                enemy_transform.scale.truncate(),
-            );
-            if collision_bullet_enemy.is_some() {
-                commands.entity(bullet_entity).despawn();
-                commands.entity(enemy_entity).despawn();
-
-                destroyed_enemy_count.0 += 1;
-                collision_bullet_enemy_event.send_default();
-                break;
-            }
+    // Enemies are only despawned once the tick ends, so the ones already shot
+    // are skipped to never kill an enemy twice
+    let mut killed_enemies = HashSet::new();
+    split_children.0 = 0;
+    for (bullet_entity, bullet_transform, previous_position, bullet_shape, damage, direction) in
+        bullet_query.iter()
+    {
+        // A fast bullet can skip over an enemy in a single tick, so the whole
+        // stretch it covered is checked
+        let (bullet_position, bullet_shape) =
+            bullet_shape.swept(previous_position.0, bullet_transform.translation.truncate());
+        let hit = enemy_spatial_hash
+            .0
+            .query(bullet_position, &bullet_shape)
+            .find(|(enemy_entity, enemy_position, enemy_shape)| {
+                !killed_enemies.contains(enemy_entity)
+                    && bullet_shape.intersects(bullet_position, enemy_shape, *enemy_position)
+            });
+        let Some((enemy_entity, enemy_position, _)) = hit else {
+            continue;
+        };
+        commands.entity(bullet_entity).despawn();
+        let Ok((mut health, kind, boss)) = enemy_query.get_mut(enemy_entity) else {
+            continue;
+        };
+        health.current = health.current.saturating_sub(damage.0);
+        enemy_hit_event.send(EnemyHitEvent {
+            enemy: enemy_entity,
+            position: enemy_position,
+            bullet: bullet_entity,
+            damage: damage.0,
+        });
+        if health.current > 0 {
+            continue;
+        }
+        killed_enemies.insert(enemy_entity);
+        commands.entity(enemy_entity).despawn();
+
+        destroyed_enemy_count.0 += 1;
+        let previous = score.0;
+        score.0 += kind.score;
+        enemy_killed_event.send(EnemyKilledEvent {
+            enemy: enemy_entity,
+            position: enemy_position,
+            bullet: bullet_entity,
+            score_delta: kind.score,
+        });
+        if boss {
+            boss_defeated_event.send(BossDefeatedEvent {
+                boss: enemy_entity,
+                name: kind.name.clone(),
+                position: enemy_position,
+                score_delta: kind.score,
+            });
+        }
+        score_changed_event.send(ScoreChangedEvent {
+            previous,
+            score: score.0,
+        });
+
+        // Children fly apart across the bullet's path. They all come out even
+        // past the enemy cap, holding back the wave's spawns instead.
+        let (Some(child), Some(children)) = (
+            kind.child(),
+            kind.split.as_ref().map(|split| split.children),
+        ) else {
+            continue;
+        };
+        split_children.0 += children as usize;
+        let bullet_direction = Vec2::new(direction.x, direction.y);
+        for index in 0..children {
+            let angle = TAU * (index as f32 + 0.5) / children as f32;
+            let heading = Vec2::from_angle(angle).rotate(bullet_direction);
+            let enemy = commands
+                .spawn(enemy_bundle(enemy_position.x, enemy_position.y, &child))
+                .insert((
+                    Direction {
+                        x: heading.x,
+                        y: heading.y,
+                    },
+                    Scatter(SPLIT_SCATTER_SECONDS),
+                ))
+                .id();
+            enemy_spawned_event.send(EnemySpawnedEvent {
+                enemy,
+                kind: child.name.clone(),
+                side: EnemySpawnSide::Split,
+                position: enemy_position,
+            });
         }
     }
 }
 
 fn play_bullet_collide_enemy_sound(
-    mut collision_bullet_enemy_events: EventReader<CollisionBulletEnemyEvent>,
+    mut enemy_killed_events: EventReader<EnemyKilledEvent>,
     sound: Res<Sounds>,
     mut commands: Commands,
 ) {
-    if !collision_bullet_enemy_events.is_empty() {
-        collision_bullet_enemy_events.clear();
+    if !enemy_killed_events.is_empty() {
+        enemy_killed_events.clear();
         commands.spawn(AudioBundle {
             source: sound.collision_bullet_enemy.clone(),
             settings: PlaybackSettings::DESPAWN,
@@ -384,12 +901,12 @@ fn play_bullet_collide_enemy_sound(
 }
 
 fn play_game_over_sound(
-    mut collision_bullet_enemy_events: EventReader<GameOverEvent>,
+    mut player_died_events: EventReader<PlayerDiedEvent>,
     sound: Res<Sounds>,
     mut commands: Commands,
 ) {
-    if !collision_bullet_enemy_events.is_empty() {
-        collision_bullet_enemy_events.clear();
+    if !player_died_events.is_empty() {
+        player_died_events.clear();
         commands.spawn(AudioBundle {
             source: sound.game_over.clone(),
             settings: PlaybackSettings::DESPAWN,
@@ -397,8 +914,24 @@ fn play_game_over_sound(
     }
 }
 
-fn gen_rand(rng: &mut Rng, min: f32, max: f32) -> f32 {
-    rng.0.gen::<f32>() * (max - min) + min
+// There is no sound of its own for bosses, the kill sound is slowed down to a
+// rumble instead
+fn play_boss_defeated_sound(
+    mut boss_defeated_events: EventReader<BossDefeatedEvent>,
+    sound: Res<Sounds>,
+    mut commands: Commands,
+) {
+    if !boss_defeated_events.is_empty() {
+        boss_defeated_events.clear();
+        commands.spawn(AudioBundle {
+            source: sound.collision_bullet_enemy.clone(),
+            settings: PlaybackSettings::DESPAWN.with_speed(BOSS_DEFEATED_SOUND_SPEED),
+        });
+    }
+}
+
+fn gen_rand(rng: &mut Pcg32, min: f32, max: f32) -> f32 {
+    rng.gen::<f32>() * (max - min) + min
 }
 
 fn normalize_direction(direction: &mut Direction) {
@@ -407,85 +940,248 @@ fn normalize_direction(direction: &mut Direction) {
     direction.y /= norm;
 }
 
+type MovingEnemy = (
+    Entity,
+    &'static mut Direction,
+    &'static mut Transform,
+    &'static CollisionShape,
+    &'static EnemyKind,
+    &'static Health,
+    &'static mut BehaviorState,
+    Option<&'static mut Scatter>,
+);
+
 #[allow(clippy::too_many_arguments)]
 fn spawn_and_move_enemies(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
     query_player: Query<&Transform, (With<Player>, Without<Enemy>)>,
-    mut query_enemy: Query<(&mut Direction, &mut Transform), With<Enemy>>,
-    mut rng: Local<Rng>,
+    mut query_enemy: Query<MovingEnemy, With<Enemy>>,
+    enemy_table: Res<EnemyTable>,
+    behaviors: Res<EnemyBehaviors>,
+    player_input: Res<PlayerInput>,
+    enemy_spatial_hash: Res<EnemySpatialHash>,
+    enemy_separation: Res<EnemySeparation>,
+    flow_field: Res<FlowField>,
+    walls: Walls,
+    arena_bounds: Res<ArenaBounds>,
+    mut rng: ResMut<GameRng>,
     time: Res<Time>,
-    mut time_since: Local<TimeSince>,
+    mut waves: Waves,
     destroyed_enemy_count: Res<DestroyedEnemyCount>,
+    mut enemy_spawned_event: EventWriter<EnemySpawnedEvent>,
 ) {
     let player_position = query_player.single().translation;
-    time_since.0 += time.delta_seconds();
-    let allow_new_spawn = if time_since.0 > 0.4 {
-        time_since.0 = 0.0;
-        true
-    } else {
-        false
-    };
-
-    for (mut direction, mut transform) in query_enemy.iter_mut() {
-        //if rng.0.gen::<f32>() > 0.95 {
+
+    let walls = walls.boxes();
+    let player_velocity =
+        Vec2::new(player_input.movement.x, player_input.movement.y) * PLAYER_SPEED;
+    for (entity, mut direction, mut transform, shape, kind, health, mut behavior_state, scatter) in
+        query_enemy.iter_mut()
+    {
+        //if rng.gen::<f32>() > 0.95 {
         let enemy_position = transform.translation;
-        let new_direction = player_position - enemy_position;
-        direction.x = new_direction.x;
-        direction.y = new_direction.y;
-        normalize_direction(&mut direction);
-
-        if (direction.x > 0. && transform.translation.x > ENEMY_RIGHT_BOUNDARY)
-            || (direction.x < 0. && transform.translation.x < ENEMY_LEFT_BOUNDARY)
-        {
-            direction.x = -direction.x;
-        }
-        if (direction.y > 0. && transform.translation.y > ENEMY_TOP_BOUNDARY)
-            || (direction.y < 0. && transform.translation.y < ENEMY_BOTTOM_BOUNDARY)
-        {
-            direction.y = -direction.y;
+        let to_player = (player_position - enemy_position).truncate();
+        // Straight at the player when nothing is in the way, around the walls
+        // otherwise
+        let in_sight = clear_path(
+            shape,
+            enemy_position.truncate(),
+            player_position.truncate(),
+            &walls,
+        );
+        let path = if in_sight {
+            to_player
+        } else {
+            flow_field
+                .direction(enemy_position.truncate())
+                .unwrap_or(to_player)
+        };
+        let speed = kind.speed
+            * INITIAL_ENEMY_SPEED_FACTOR
+            * (1. + destroyed_enemy_count.0 as f32 * kind.speed_ramp);
+
+        let pace = match scatter {
+            // Keeps the direction it was sent in at full pace
+            Some(mut scatter) if scatter.0 > 0. => {
+                scatter.0 -= time.delta_seconds();
+                1.
+            }
+            _ => {
+                // Tables inserted by code aren't checked, their unknown
+                // behaviors and ones with problems fall back to chasing
+                let behavior = behaviors
+                    .get(&kind.behavior)
+                    .filter(|behavior| behavior.problem().is_none())
+                    .unwrap_or(&Chase);
+                let heading = behavior.steer(
+                    &Steering {
+                        position: enemy_position.truncate(),
+                        player: player_position.truncate(),
+                        player_velocity,
+                        path: path.normalize_or_zero(),
+                        in_sight,
+                        speed,
+                        health: health.fraction(),
+                        delta: time.delta_seconds(),
+                    },
+                    &mut behavior_state,
+                    rng.stream(RngStream::EnemyBehavior),
+                );
+                direction.x = heading.x;
+                direction.y = heading.y;
+                normalize_direction(&mut direction);
+                // Behaviors slow enemies down with shorter headings
+                heading.length()
+            }
+        };
+
+        // Steer away from nearby enemies, so that they spread out around the
+        // player instead of piling up
+        let push = enemy_separation.push(
+            entity,
+            enemy_position.truncate(),
+            shape,
+            &enemy_spatial_hash.0,
+        );
+        if push != Vec2::ZERO {
+            direction.x += push.x;
+            direction.y += push.y;
+            normalize_direction(&mut direction);
         }
-        transform.translation.x += INITIAL_ENEMY_SPEED_FACTOR
-            * time.delta_seconds()
-            * direction.x
-            * (1. + destroyed_enemy_count.0 as f32 * 0.08);
-        transform.translation.y += INITIAL_ENEMY_SPEED_FACTOR
-            * time.delta_seconds()
-            * direction.y
-            * (1. + destroyed_enemy_count.0 as f32 * 0.08);
+
+        let movement = Vec2::new(direction.x, direction.y) * speed * pace * time.delta_seconds();
+        let (position, _) =
+            move_against_walls(shape, enemy_position.truncate(), movement, &walls, false);
+        transform.translation = position.extend(enemy_position.z);
     }
 
-    if query_enemy.iter().len() >= MAX_NUMBER_OF_ENEMIES || !allow_new_spawn {
-        return;
+    // Enemies killed this tick are only despawned once it ends
+    let alive = query_enemy
+        .iter()
+        .filter(|(_, _, _, _, _, health, _, _)| health.current > 0)
+        .count();
+    let (spawns, boss) = waves.advance(time.delta_seconds(), alive);
+    if let Some(boss) = boss {
+        // Comes in from the middle of the top wall
+        let kind = boss.kind(0);
+        let (position, side) = spawn_position(
+            SpawnFrom::Point(0., arena_bounds.max.y),
+            &kind,
+            &arena_bounds,
+            rng.stream(RngStream::EnemySpawn),
+        );
+        let enemy = commands
+            .spawn((
+                enemy_bundle(position.x, position.y, &kind),
+                Boss {
+                    kind: boss,
+                    phase: 0,
+                },
+            ))
+            .id();
+        enemy_spawned_event.send(EnemySpawnedEvent {
+            enemy,
+            kind: kind.name,
+            side,
+            position,
+        });
     }
 
-    //let mut direction = default();
-    //randomize_direction(&mut direction, &mut rng);
-    let rand = rng.0.gen::<f32>();
-    let x;
-    let y;
-    if rand > 0.66 {
-        x = gen_rand(&mut rng, ENEMY_LEFT_BOUNDARY, ENEMY_RIGHT_BOUNDARY);
-        y = ENEMY_TOP_BOUNDARY;
-    } else if rand > 0.33 {
-        x = ENEMY_LEFT_BOUNDARY;
-        y = gen_rand(&mut rng, 0., ENEMY_TOP_BOUNDARY);
-    } else {
-        x = ENEMY_RIGHT_BOUNDARY;
-        y = gen_rand(&mut rng, 0., ENEMY_TOP_BOUNDARY);
+    let rng = rng.stream(RngStream::EnemySpawn);
+    for spawn in spawns {
+        let kind = match spawn.kind.as_deref().and_then(|name| enemy_table.get(name)) {
+            Some(kind) => kind,
+            None => enemy_table.pick(rng),
+        };
+        let (position, side) = spawn_position(spawn.from, kind, &arena_bounds, rng);
+        let enemy = commands
+            .spawn(enemy_bundle(position.x, position.y, kind))
+            .id();
+        enemy_spawned_event.send(EnemySpawnedEvent {
+            enemy,
+            kind: kind.name.clone(),
+            side,
+            position,
+        });
     }
+}
+
+fn spawn_position(
+    from: SpawnFrom,
+    kind: &EnemyKind,
+    arena_bounds: &ArenaBounds,
+    rng: &mut Pcg32,
+) -> (Vec2, EnemySpawnSide) {
+    // Enemies appear a little away from the walls
+    let margin = WALL_THICKNESS / 2. + kind.size / 2.;
+    let left = arena_bounds.min.x + margin;
+    let right = arena_bounds.max.x - margin;
+    let top = arena_bounds.max.y - margin;
+    // Enemies coming in from the sides start above the middle of the screen
+    let lowest = 0f32.clamp(arena_bounds.min.y + margin, top);
 
-    commands.spawn((
+    match from {
+        SpawnFrom::Edge => {
+            let rand = rng.gen::<f32>();
+            let edge = if rand > 0.66 {
+                SpawnFrom::Top
+            } else if rand > 0.33 {
+                SpawnFrom::Left
+            } else {
+                SpawnFrom::Right
+            };
+            spawn_position(edge, kind, arena_bounds, rng)
+        }
+        SpawnFrom::Top => (
+            Vec2::new(gen_rand(rng, left, right), top),
+            EnemySpawnSide::Top,
+        ),
+        SpawnFrom::Left => (
+            Vec2::new(left, gen_rand(rng, lowest, top)),
+            EnemySpawnSide::Left,
+        ),
+        SpawnFrom::Right => (
+            Vec2::new(right, gen_rand(rng, lowest, top)),
+            EnemySpawnSide::Right,
+        ),
+        SpawnFrom::Point(x, y) => (
+            Vec2::new(x, y).clamp(
+                Vec2::new(left, arena_bounds.min.y + margin),
+                Vec2::new(right, top),
+            ),
+            EnemySpawnSide::Point,
+        ),
+    }
+}
+
+fn enemy_bundle(
+    x: f32,
+    y: f32,
+    kind: &EnemyKind,
+) -> (
+    Direction,
+    SpatialBundle,
+    CollisionShape,
+    Health,
+    EnemyGun,
+    BehaviorState,
+    EnemyKind,
+    Enemy,
+) {
+    (
         Direction::default(),
-        MaterialMesh2dBundle {
-            mesh: meshes.add(Mesh::from(shape::Quad::default())).into(),
-            transform: Transform::from_xyz(x, y, 0.).with_scale(Vec3::splat(ENEMY_SIZE)),
-            material: materials.add(ColorMaterial::from(Color::MIDNIGHT_BLUE)),
-            ..default()
+        SpatialBundle::from_transform(
+            Transform::from_xyz(x, y, 0.).with_scale(Vec3::splat(kind.size)),
+        ),
+        CollisionShape::Box {
+            half_size: Vec2::splat(kind.size / 2.),
         },
+        Health::new(kind.hit_points),
+        EnemyGun::new(kind),
+        BehaviorState::default(),
+        kind.clone(),
         Enemy,
-    ));
+    )
 }
 
 fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
@@ -499,92 +1195,161 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
 
 fn check_player_collide_enemy(
     mut commands: Commands,
-    player_query: Query<(Entity, &Transform), With<Player>>,
-    enemy_query: Query<(Entity, &Transform), With<Enemy>>,
+    player_query: Query<(Entity, &Transform, &CollisionShape), With<Player>>,
+    enemy_spatial_hash: Res<EnemySpatialHash>,
     mut app_state: ResMut<NextState<AppState>>,
-    mut game_over_event: EventWriter<GameOverEvent>,
+    mut player_died_event: EventWriter<PlayerDiedEvent>,
+    run_stats: Res<RunStats>,
+    time: Res<Time>,
 ) {
-    for (player_entity, player_transform) in player_query.iter() {
-        for (_, enemy_transform) in enemy_query.iter() {
-            let collision = collide(
-                player_transform.translation,
-                player_transform.scale.truncate(),
-                enemy_transform.translation,
-                enemy_transform.scale.truncate(),
-            );
-            if collision.is_some() {
-                app_state.set(AppState::GameOver);
-                commands.entity(player_entity).despawn();
-                game_over_event.send_default();
-                break;
-            }
+    for (player_entity, player_transform, player_shape) in player_query.iter() {
+        let player_position = player_transform.translation.truncate();
+        let killer = enemy_spatial_hash
+            .0
+            .query(player_position, player_shape)
+            .find(|(_, enemy_position, enemy_shape)| {
+                player_shape.intersects(player_position, enemy_shape, *enemy_position)
+            });
+        if let Some((enemy_entity, _, _)) = killer {
+            app_state.set(AppState::GameOver);
+            commands.entity(player_entity).despawn();
+            player_died_event.send(PlayerDiedEvent {
+                killer: enemy_entity,
+                cause: DeathCause::EnemyContact,
+                position: player_transform.translation.truncate(),
+                // The run statistics only count this tick once it ends
+                survival_time: run_stats.survival_time + time.delta_seconds(),
+            });
         }
     }
 }
 
+type HittingEnemyBullet = (
+    Entity,
+    &'static Transform,
+    &'static PreviousPosition,
+    &'static CollisionShape,
+);
+
+fn check_player_collide_enemy_bullet(
+    mut commands: Commands,
+    player_query: Query<(Entity, &Transform, &CollisionShape), With<Player>>,
+    bullet_query: Query<HittingEnemyBullet, With<EnemyBullet>>,
+    mut app_state: ResMut<NextState<AppState>>,
+    mut player_died_event: EventWriter<PlayerDiedEvent>,
+    run_stats: Res<RunStats>,
+    time: Res<Time>,
+) {
+    // Already killed by an enemy earlier in the tick
+    if app_state.0 == Some(AppState::GameOver) {
+        return;
+    }
+    for (player_entity, player_transform, player_shape) in player_query.iter() {
+        let player_position = player_transform.translation.truncate();
+        let killer =
+            bullet_query
+                .iter()
+                .find(|(_, bullet_transform, previous_position, bullet_shape)| {
+                    let (bullet_position, bullet_shape) = bullet_shape
+                        .swept(previous_position.0, bullet_transform.translation.truncate());
+                    bullet_shape.intersects(bullet_position, player_shape, player_position)
+                });
+        if let Some((bullet_entity, ..)) = killer {
+            app_state.set(AppState::GameOver);
+            commands.entity(player_entity).despawn();
+            commands.entity(bullet_entity).despawn();
+            player_died_event.send(PlayerDiedEvent {
+                killer: bullet_entity,
+                cause: DeathCause::EnemyBullet,
+                position: player_position,
+                survival_time: run_stats.survival_time + time.delta_seconds(),
+            });
+        }
+    }
+}
+
+// Everything that goes away when a new game starts
+type ClearedOnRestart = Or<(With<Enemy>, With<EnemyBullet>)>;
+
+#[allow(clippy::too_many_arguments)]
 fn game_restarter(
     mut commands: Commands,
     mut app_state: ResMut<NextState<AppState>>,
-    mut query: Query<Entity, With<Enemy>>,
+    mut query: Query<Entity, ClearedOnRestart>,
     keyboard_input: Res<Input<KeyCode>>,
     mut destroyed_enemy_count: ResMut<DestroyedEnemyCount>,
+    mut score: ResMut<Score>,
+    mut score_changed_event: EventWriter<ScoreChangedEvent>,
+    mut wave_director: ResMut<WaveDirector>,
     time: Res<Time>,
-    mut time_since: Local<TimeSince>,
+    mut restart_timer: ResMut<RestartTimer>,
 ) {
-    time_since.0 += time.delta_seconds();
-    if time_since.0 > 3.0 || keyboard_input.pressed(KeyCode::R) {
+    restart_timer.0 += time.delta_seconds();
+    if restart_timer.0 > 3.0 || keyboard_input.pressed(KeyCode::R) {
         for entity in query.iter_mut() {
             commands.entity(entity).despawn();
         }
         app_state.set(AppState::GameStart);
+        if score.0 != 0 {
+            score_changed_event.send(ScoreChangedEvent {
+                previous: score.0,
+                score: 0,
+            });
+        }
         destroyed_enemy_count.0 = 0;
-        time_since.0 = 0.0;
+        score.0 = 0;
+        *wave_director = WaveDirector::default();
+        restart_timer.0 = 0.0;
     }
 }
 
-fn spawn_player(
-    mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut app_state: ResMut<NextState<AppState>>,
-) {
-    // Swawn Player
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: meshes.add(shape::Circle::default().into()).into(),
-            material: materials.add(ColorMaterial::from(Color::LIME_GREEN)),
-            transform: Transform::from_xyz(0., PLAYER_BOTTOM_BOUNDARY * 0.9, 0.)
-                .with_scale(Vec3::splat(PLAYER_SIZE)),
-            ..default()
+fn spawn_player(mut commands: Commands, mut app_state: ResMut<NextState<AppState>>) {
+    commands.spawn(player_bundle(Vec3::new(
+        0.,
+        PLAYER_BOTTOM_BOUNDARY * 0.9,
+        0.,
+    )));
+
+    if cfg!(feature = "mobile") {
+        let (left_joystick, right_joystick) = joystick_bundles();
+        commands.spawn(left_joystick);
+        commands.spawn(right_joystick);
+    }
+
+    app_state.set(AppState::InGame);
+}
+
+fn player_bundle(translation: Vec3) -> (SpatialBundle, CollisionShape, Player) {
+    (
+        SpatialBundle::from_transform(
+            Transform::from_translation(translation).with_scale(Vec3::splat(PLAYER_SIZE)),
+        ),
+        // Drawn as a circle, so hit as one
+        CollisionShape::Circle {
+            radius: PLAYER_SIZE / 2.,
         },
         Player,
-    ));
+    )
+}
 
-    if cfg!(feature = "mobile") {
-        // Swawn LeftJoystick
-        commands.spawn((
-            MaterialMesh2dBundle {
-                mesh: meshes.add(shape::Circle::default().into()).into(),
-                material: materials.add(ColorMaterial::from(Color::BLACK)),
-                transform: Transform::from_xyz(LEFT_JOYSTICK_X, JOYSTICK_Y, 1.0)
+fn joystick_bundles() -> (
+    (SpatialBundle, LeftJoyStick),
+    (SpatialBundle, RightJoyStick),
+) {
+    (
+        (
+            SpatialBundle::from_transform(
+                Transform::from_xyz(LEFT_JOYSTICK_X, JOYSTICK_Y, 1.0)
                     .with_scale(Vec3::splat(JOYSTICK_SIZE)),
-                ..default()
-            },
+            ),
             LeftJoyStick,
-        ));
-
-        // Swawn RightJoystick
-        commands.spawn((
-            MaterialMesh2dBundle {
-                mesh: meshes.add(shape::Circle::default().into()).into(),
-                material: materials.add(ColorMaterial::from(Color::BLACK)),
-                transform: Transform::from_xyz(RIGHT_JOYSTICK_X, JOYSTICK_Y, 1.0)
+        ),
+        (
+            SpatialBundle::from_transform(
+                Transform::from_xyz(RIGHT_JOYSTICK_X, JOYSTICK_Y, 1.0)
                     .with_scale(Vec3::splat(JOYSTICK_SIZE)),
-                ..default()
-            },
+            ),
             RightJoyStick,
-        ));
-    }
-
-    app_state.set(AppState::InGame);
+        ),
+    )
 }
@@ -1,27 +1,141 @@
 use bevy::prelude::*;
-use bevy_shoot_em_up::GamePlugin;
+use bevy_shoot_em_up::{
+    bot_by_name, ActiveBot, GamePlugin, InputSource, RecordPlugin, Replay, ReplayPlugin,
+    RestorePlugin, Snapshot, SnapshotPlugin, BOT_NAMES, DEFAULT_TICK_RATE,
+};
+
+// Value following `name` on the command line
+fn arg(name: &str) -> Option<String> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == name {
+            return args.next();
+        }
+    }
+    None
+}
+
+// Seed for the game's random numbers, taken from `--seed <n>` or, failing that,
+// from the `SHOOT_EM_UP_SEED` environment variable
+fn seed() -> Option<u64> {
+    arg("--seed")
+        .or_else(|| std::env::var("SHOOT_EM_UP_SEED").ok())
+        .and_then(|seed| seed.parse().ok())
+}
+
+// Ticks per second from `--tick-rate <hz>`, which must be a number above 0
+fn tick_rate() -> f64 {
+    let Some(tick_rate) = arg("--tick-rate") else {
+        return DEFAULT_TICK_RATE;
+    };
+    match tick_rate.parse::<f64>() {
+        Ok(hz) if hz.is_finite() && hz > 0. => hz,
+        _ => {
+            eprintln!(
+                "invalid tick rate {tick_rate}, expected a number of ticks per second above 0"
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn game_plugin() -> GamePlugin {
+    GamePlugin {
+        seed: seed(),
+        tick_rate: tick_rate(),
+        ..default()
+    }
+}
+
+// `--bot <name>` lets one of the built-in bots play
+fn add_bot(app: &mut App) {
+    if let Some(name) = arg("--bot") {
+        match bot_by_name(&name) {
+            Some(bot) => {
+                app.insert_resource(ActiveBot(bot))
+                    .insert_resource(InputSource::Bot);
+            }
+            None => {
+                eprintln!(
+                    "unknown bot {name}, expected one of {}",
+                    BOT_NAMES.join(", ")
+                );
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+// `--record <path>` saves each game to a replay file, `--replay <path>` plays
+// one back
+fn add_replay_plugins(app: &mut App) {
+    if let Some(path) = arg("--record") {
+        app.add_plugins(RecordPlugin { path: path.into() });
+    }
+    if let Some(path) = arg("--replay") {
+        match Replay::load(&path) {
+            Ok(replay) => {
+                app.add_plugins(ReplayPlugin { replay });
+            }
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+// F5 saves the game to `--snapshot <path>` (`snapshot.json` by default) and F9
+// restores it, `--load <path>` starts from a saved snapshot
+fn add_snapshot_plugins(app: &mut App) {
+    app.add_plugins(SnapshotPlugin {
+        path: arg("--snapshot")
+            .unwrap_or_else(|| "snapshot.json".to_string())
+            .into(),
+    });
+    if let Some(path) = arg("--load") {
+        match Snapshot::load(&path) {
+            Ok(snapshot) => {
+                app.add_plugins(RestorePlugin { snapshot });
+            }
+            Err(err) => {
+                eprintln!("{path}: {err}");
+                std::process::exit(1);
+            }
+        }
+    }
+}
 
 #[cfg(not(feature = "mobile"))]
 #[bevy_main]
 fn main() {
-    App::new().add_plugins((DefaultPlugins, GamePlugin)).run();
+    let mut app = App::new();
+    app.add_plugins((DefaultPlugins, game_plugin()));
+    add_bot(&mut app);
+    add_replay_plugins(&mut app);
+    add_snapshot_plugins(&mut app);
+    app.run();
 }
 
 #[cfg(feature = "mobile")]
 #[bevy_main]
 fn main() {
     use bevy::window::WindowResolution;
+    use bevy_shoot_em_up::{MOBILE_WINDOW_HEIGHT, MOBILE_WINDOW_WIDTH};
 
-    App::new()
-        .add_plugins((
-            DefaultPlugins.set(WindowPlugin {
-                primary_window: Some(Window {
-                    resolution: WindowResolution::new(900., 1500.),
-                    ..default()
-                }),
+    let mut app = App::new();
+    app.add_plugins((
+        DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(MOBILE_WINDOW_WIDTH, MOBILE_WINDOW_HEIGHT),
                 ..default()
             }),
-            GamePlugin,
-        ))
-        .run();
+            ..default()
+        }),
+        game_plugin(),
+    ));
+    add_bot(&mut app);
+    add_replay_plugins(&mut app);
+    add_snapshot_plugins(&mut app);
+    app.run();
 }
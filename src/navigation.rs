@@ -0,0 +1,212 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+use bevy::prelude::*;
+
+use crate::{ArenaBounds, CollisionShape, Enemy, Player, Walls, ENEMY_SIZE};
+
+/// Side of the square cells enemies find their way through the arena with
+pub const NAVIGATION_CELL_SIZE: f32 = ENEMY_SIZE;
+
+// Costs of stepping to a neighbouring cell, straight or diagonally
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+const NEIGHBOURS: [(IVec2, u32); 8] = [
+    (IVec2::new(0, -1), STRAIGHT_COST),
+    (IVec2::new(-1, 0), STRAIGHT_COST),
+    (IVec2::new(1, 0), STRAIGHT_COST),
+    (IVec2::new(0, 1), STRAIGHT_COST),
+    (IVec2::new(-1, -1), DIAGONAL_COST),
+    (IVec2::new(1, -1), DIAGONAL_COST),
+    (IVec2::new(-1, 1), DIAGONAL_COST),
+    (IVec2::new(1, 1), DIAGONAL_COST),
+];
+
+const UNREACHABLE: u32 = u32::MAX;
+
+/// Length of the shortest way from every cell of the arena to a target,
+/// going around the walls.
+///
+/// Enemies get to the player by always stepping to the neighbouring cell
+/// closest to it. Cells the biggest enemy can't stand in without touching a
+/// wall are left out, so the way fits every enemy, and the field is only
+/// rebuilt when the target moves to another cell or the walls or the biggest
+/// enemy's size change.
+#[derive(Resource, Default)]
+pub struct FlowField {
+    origin: Vec2,
+    size: IVec2,
+    walls: Vec<(Vec2, Vec2)>,
+    clearance: f32,
+    blocked: Vec<bool>,
+    target: Option<IVec2>,
+    distances: Vec<u32>,
+}
+
+impl FlowField {
+    /// Points the field at `target` inside `bounds`, around `walls` given as
+    /// box centers and half sizes, keeping `clearance` away from them
+    pub fn update(
+        &mut self,
+        bounds: &ArenaBounds,
+        walls: &[(Vec2, Vec2)],
+        clearance: f32,
+        target: Vec2,
+    ) {
+        let size = ((bounds.max - bounds.min) / NAVIGATION_CELL_SIZE)
+            .ceil()
+            .as_ivec2()
+            .max(IVec2::ONE);
+        let walls_changed = bounds.min != self.origin
+            || size != self.size
+            || walls != self.walls
+            || clearance != self.clearance;
+        if walls_changed {
+            self.origin = bounds.min;
+            self.size = size;
+            self.walls = walls.to_vec();
+            self.clearance = clearance;
+            self.block_cells();
+        }
+
+        let target = self.cell(target);
+        if walls_changed || self.target != Some(target) {
+            self.target = Some(target);
+            self.find_distances(target);
+        }
+    }
+
+    /// Which way to go from `position` to get to the target, or `None` from
+    /// the target's own cell or when there is no way there
+    pub fn direction(&self, position: Vec2) -> Option<Vec2> {
+        if self.distances.is_empty() {
+            return None;
+        }
+        let cell = self.cell(position);
+        let mut best = (self.distance(cell), None);
+        for (offset, _) in NEIGHBOURS {
+            let neighbour = cell + offset;
+            if !self.contains(neighbour) || !self.can_step(cell, offset) {
+                continue;
+            }
+            let distance = self.distance(neighbour);
+            if distance < best.0 {
+                best = (distance, Some(neighbour));
+            }
+        }
+        let (_, next) = best;
+        next.map(|next| (self.center(next) - position).normalize_or_zero())
+    }
+
+    /// Cost of the shortest way from `position` to the target, in cells
+    pub fn distance_to_target(&self, position: Vec2) -> Option<f32> {
+        if self.distances.is_empty() {
+            return None;
+        }
+        match self.distance(self.cell(position)) {
+            UNREACHABLE => None,
+            distance => Some(distance as f32 / STRAIGHT_COST as f32),
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        ((position - self.origin) / NAVIGATION_CELL_SIZE)
+            .floor()
+            .as_ivec2()
+            .clamp(IVec2::ZERO, self.size - 1)
+    }
+
+    fn center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * NAVIGATION_CELL_SIZE
+    }
+
+    fn contains(&self, cell: IVec2) -> bool {
+        cell.cmpge(IVec2::ZERO).all() && cell.cmplt(self.size).all()
+    }
+
+    fn index(&self, cell: IVec2) -> usize {
+        (cell.y * self.size.x + cell.x) as usize
+    }
+
+    fn distance(&self, cell: IVec2) -> u32 {
+        self.distances[self.index(cell)]
+    }
+
+    fn is_blocked(&self, cell: IVec2) -> bool {
+        self.blocked[self.index(cell)]
+    }
+
+    // Diagonal steps can't cut the corner of a wall
+    fn can_step(&self, cell: IVec2, offset: IVec2) -> bool {
+        let neighbour = cell + offset;
+        !self.is_blocked(neighbour)
+            && (offset.x == 0
+                || offset.y == 0
+                || !(self.is_blocked(IVec2::new(neighbour.x, cell.y))
+                    || self.is_blocked(IVec2::new(cell.x, neighbour.y))))
+    }
+
+    fn block_cells(&mut self) {
+        let half_size = Vec2::splat(self.clearance);
+        self.blocked = (0..self.size.y)
+            .flat_map(|y| (0..self.size.x).map(move |x| IVec2::new(x, y)))
+            .map(|cell| {
+                let center = self.center(cell);
+                self.walls.iter().any(|&(wall_center, wall_half_size)| {
+                    let gap = (center - wall_center).abs() - wall_half_size - half_size;
+                    gap.x < 0. && gap.y < 0.
+                })
+            })
+            .collect();
+    }
+
+    // Dijkstra's algorithm from the target outwards
+    fn find_distances(&mut self, target: IVec2) {
+        self.distances = vec![UNREACHABLE; self.blocked.len()];
+        let index = self.index(target);
+        self.distances[index] = 0;
+        // Ties are broken by cell, for the same field every time
+        let mut queue = BinaryHeap::from([Reverse((0, target.y, target.x))]);
+        while let Some(Reverse((distance, y, x))) = queue.pop() {
+            let cell = IVec2::new(x, y);
+            if distance > self.distance(cell) {
+                continue;
+            }
+            for (offset, cost) in NEIGHBOURS {
+                let neighbour = cell + offset;
+                if !self.contains(neighbour) || !self.can_step(cell, offset) {
+                    continue;
+                }
+                let index = self.index(neighbour);
+                if distance + cost < self.distances[index] {
+                    self.distances[index] = distance + cost;
+                    queue.push(Reverse((distance + cost, neighbour.y, neighbour.x)));
+                }
+            }
+        }
+    }
+}
+
+pub fn update_flow_field(
+    query_player: Query<&Transform, With<Player>>,
+    query_enemy: Query<&CollisionShape, With<Enemy>>,
+    walls: Walls,
+    arena_bounds: Res<ArenaBounds>,
+    mut flow_field: ResMut<FlowField>,
+) {
+    // Wide enough for the biggest enemy around, or a regular one while there
+    // are none
+    let clearance = query_enemy
+        .iter()
+        .map(|shape| shape.half_extents().max_element())
+        .reduce(f32::max)
+        .unwrap_or(ENEMY_SIZE / 2.);
+    if let Ok(player) = query_player.get_single() {
+        flow_field.update(
+            &arena_bounds,
+            &walls.boxes(),
+            clearance,
+            player.translation.truncate(),
+        );
+    }
+}
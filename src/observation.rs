@@ -0,0 +1,65 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{Bullet, DestroyedEnemyCount, Direction, Enemy, EnemyBullet, Player, Score};
+
+/// What an agent playing the game can see
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    /// Missing once the player has died
+    pub player: Option<[f32; 2]>,
+    pub enemies: Vec<EnemyObservation>,
+    pub bullets: Vec<[f32; 2]>,
+    pub enemy_bullets: Vec<[f32; 2]>,
+    pub destroyed_enemy_count: u32,
+    pub score: u32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EnemyObservation {
+    pub position: [f32; 2],
+    pub direction: [f32; 2],
+}
+
+// Read access to everything that makes up an `Observation`
+#[derive(SystemParam)]
+pub(crate) struct Observer<'w, 's> {
+    player_query: Query<'w, 's, &'static Transform, With<Player>>,
+    enemy_query: Query<'w, 's, (&'static Transform, &'static Direction), With<Enemy>>,
+    bullet_query: Query<'w, 's, &'static Transform, With<Bullet>>,
+    enemy_bullet_query: Query<'w, 's, &'static Transform, With<EnemyBullet>>,
+    destroyed_enemy_count: Res<'w, DestroyedEnemyCount>,
+    score: Res<'w, Score>,
+}
+
+impl Observer<'_, '_> {
+    pub(crate) fn observation(&self) -> Observation {
+        Observation {
+            player: self
+                .player_query
+                .get_single()
+                .ok()
+                .map(|transform| transform.translation.truncate().to_array()),
+            enemies: self
+                .enemy_query
+                .iter()
+                .map(|(transform, direction)| EnemyObservation {
+                    position: transform.translation.truncate().to_array(),
+                    direction: [direction.x, direction.y],
+                })
+                .collect(),
+            bullets: self
+                .bullet_query
+                .iter()
+                .map(|transform| transform.translation.truncate().to_array())
+                .collect(),
+            enemy_bullets: self
+                .enemy_bullet_query
+                .iter()
+                .map(|transform| transform.translation.truncate().to_array())
+                .collect(),
+            destroyed_enemy_count: self.destroyed_enemy_count.0,
+            score: self.score.0,
+        }
+    }
+}
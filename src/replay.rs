@@ -0,0 +1,361 @@
+use std::{error::Error, fmt, fs, io, path::Path, path::PathBuf, time::Duration};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    check_player_collide_enemy, spawn_and_move_enemies, AppState, DestroyedEnemyCount, GameRng,
+    InputSet, InputSource, Player, PlayerInput, Score,
+};
+
+/// Version of the replay file format written by this build
+pub const REPLAY_VERSION: u32 = 4;
+
+/// A recorded game: the seed and tick length it was played with, the player
+/// input of every in-game tick, what the game looked like after each of them
+/// and the outcome the playback is expected to reproduce.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Replay {
+    pub version: u32,
+    pub seed: u64,
+    /// Length of a gameplay tick in nanoseconds
+    pub timestep_nanos: u64,
+    pub ticks: Vec<PlayerInput>,
+    /// One for every tick, checked during playback to stop as soon as it
+    /// drifts from the recording
+    pub checks: Vec<TickCheck>,
+    pub destroyed_enemy_count: u32,
+}
+
+/// Score and player position at the end of a tick
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TickCheck {
+    pub score: u32,
+    /// Missing once the player has been removed
+    pub player: Option<[f32; 2]>,
+}
+
+impl TickCheck {
+    fn capture(score: &Score, player: &Query<&Transform, With<Player>>) -> Self {
+        Self {
+            score: score.0,
+            player: player
+                .get_single()
+                .ok()
+                .map(|transform| transform.translation.truncate().to_array()),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum ReplayError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    Version(u32),
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Io(err) => write!(f, "could not access replay file: {err}"),
+            ReplayError::Format(err) => write!(f, "invalid replay file: {err}"),
+            ReplayError::Version(version) => write!(
+                f,
+                "replay file version {version} is not supported (expected {REPLAY_VERSION})"
+            ),
+        }
+    }
+}
+
+impl Error for ReplayError {}
+
+impl From<io::Error> for ReplayError {
+    fn from(err: io::Error) -> Self {
+        ReplayError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for ReplayError {
+    fn from(err: serde_json::Error) -> Self {
+        ReplayError::Format(err)
+    }
+}
+
+// Only the version is parsed first, so that files from other versions are
+// reported as such instead of as malformed
+#[derive(Deserialize)]
+struct ReplayHeader {
+    version: u32,
+}
+
+impl Replay {
+    fn new(seed: u64, timestep: Duration) -> Self {
+        Self {
+            version: REPLAY_VERSION,
+            seed,
+            timestep_nanos: timestep.as_nanos() as u64,
+            ticks: Vec::new(),
+            checks: Vec::new(),
+            destroyed_enemy_count: 0,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, ReplayError> {
+        let contents = fs::read_to_string(path)?;
+        let header: ReplayHeader = serde_json::from_str(&contents)?;
+        if header.version != REPLAY_VERSION {
+            return Err(ReplayError::Version(header.version));
+        }
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), ReplayError> {
+        fs::write(path, serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+/// Records games into a replay file at `path`.
+///
+/// A replay holds a single game: each game overwrites the file when it ends,
+/// so after several games only the last one is kept.
+pub struct RecordPlugin {
+    pub path: PathBuf,
+}
+
+#[derive(Resource)]
+struct ReplayRecorder {
+    path: PathBuf,
+    replay: Replay,
+}
+
+impl Plugin for RecordPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ReplayRecorder {
+            path: self.path.clone(),
+            replay: Replay::new(0, Duration::ZERO),
+        })
+        .add_systems(OnEnter(AppState::InGame), start_recording)
+        .add_systems(
+            FixedUpdate,
+            record_tick
+                .after(InputSet)
+                .before(check_player_collide_enemy)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            FixedUpdate,
+            record_check
+                .after(spawn_and_move_enemies)
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(OnEnter(AppState::GameOver), save_recording);
+    }
+}
+
+fn start_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    rng: Res<GameRng>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    recorder.replay = Replay::new(rng.seed(), fixed_time.timestep());
+}
+
+fn record_tick(mut recorder: ResMut<ReplayRecorder>, player_input: Res<PlayerInput>) {
+    recorder.replay.ticks.push(player_input.clone());
+}
+
+fn record_check(
+    mut recorder: ResMut<ReplayRecorder>,
+    score: Res<Score>,
+    player: Query<&Transform, With<Player>>,
+) {
+    recorder
+        .replay
+        .checks
+        .push(TickCheck::capture(&score, &player));
+}
+
+fn save_recording(
+    mut recorder: ResMut<ReplayRecorder>,
+    destroyed_enemy_count: Res<DestroyedEnemyCount>,
+) {
+    recorder.replay.destroyed_enemy_count = destroyed_enemy_count.0;
+    if let Err(err) = recorder.replay.save(&recorder.path) {
+        error!(
+            "Failed to save replay to {}: {err}",
+            recorder.path.display()
+        );
+    }
+}
+
+/// Plays `replay` back in place of live input, as the first game.
+///
+/// The seed and tick length are taken from the replay, so the playback
+/// reproduces the recorded game exactly. Live input takes over once the
+/// replayed game ends, or as soon as the playback is found to diverge from
+/// the recording, checked after every tick. Either way the [`ReplayOutcome`]
+/// resource is inserted.
+pub struct ReplayPlugin {
+    pub replay: Replay,
+}
+
+/// How the playback of a replay ended
+#[derive(Resource, Clone, Debug, PartialEq, Eq)]
+pub enum ReplayOutcome {
+    /// The game ended on the recorded tick with as many enemies destroyed as
+    /// recorded
+    Reproduced,
+    /// The score or the player's position differed from the recording after
+    /// a tick, the game went on past the recorded ticks, or it ended with a
+    /// different tick count or number of enemies destroyed than the recording
+    Diverged {
+        ticks: usize,
+        destroyed_enemy_count: u32,
+        expected_ticks: usize,
+        expected_destroyed_enemy_count: u32,
+    },
+}
+
+impl fmt::Display for ReplayOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayOutcome::Reproduced => write!(f, "replay reproduced the recorded game"),
+            ReplayOutcome::Diverged {
+                ticks,
+                destroyed_enemy_count,
+                expected_ticks,
+                expected_destroyed_enemy_count,
+            } => write!(
+                f,
+                "replay diverged after {ticks} ticks with {destroyed_enemy_count} enemies destroyed, recorded {expected_ticks} ticks with {expected_destroyed_enemy_count} enemies destroyed"
+            ),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct ReplayPlayer {
+    replay: Replay,
+    tick: usize,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(ReplayPlayer {
+            replay: self.replay.clone(),
+            tick: 0,
+        })
+        .add_systems(Startup, start_replay)
+        .add_systems(
+            FixedUpdate,
+            play_replay_tick
+                .in_set(InputSet)
+                .run_if(resource_exists::<ReplayPlayer>())
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            FixedUpdate,
+            check_replay_tick
+                .after(spawn_and_move_enemies)
+                .run_if(resource_exists::<ReplayPlayer>())
+                .run_if(in_state(AppState::InGame)),
+        )
+        .add_systems(
+            OnEnter(AppState::GameOver),
+            finish_replay.run_if(resource_exists::<ReplayPlayer>()),
+        );
+    }
+}
+
+// Runs after every plugin is built, so the replay's settings win over the
+// ones `GamePlugin` was configured with
+fn start_replay(
+    player: Res<ReplayPlayer>,
+    mut rng: ResMut<GameRng>,
+    mut fixed_time: ResMut<Time<Fixed>>,
+    mut input_source: ResMut<InputSource>,
+) {
+    *rng = GameRng::new(Some(player.replay.seed));
+    fixed_time.set_timestep(Duration::from_nanos(player.replay.timestep_nanos));
+    *input_source = InputSource::Replay;
+}
+
+fn play_replay_tick(mut player: ResMut<ReplayPlayer>, mut player_input: ResMut<PlayerInput>) {
+    *player_input = player
+        .replay
+        .ticks
+        .get(player.tick)
+        .cloned()
+        .unwrap_or_default();
+    player.tick += 1;
+}
+
+// Runs at the same point of the tick as `record_check` did. Past the last
+// recorded tick there is nothing to match, as the game should have ended.
+fn check_replay_tick(
+    mut commands: Commands,
+    player: Res<ReplayPlayer>,
+    destroyed_enemy_count: Res<DestroyedEnemyCount>,
+    score: Res<Score>,
+    player_query: Query<&Transform, With<Player>>,
+    mut input_source: ResMut<InputSource>,
+) {
+    let check = TickCheck::capture(&score, &player_query);
+    let recorded = player
+        .tick
+        .checked_sub(1)
+        .and_then(|tick| player.replay.checks.get(tick));
+    if recorded != Some(&check) {
+        stop_replay(
+            &mut commands,
+            &player,
+            destroyed_enemy_count.0,
+            &mut input_source,
+        );
+    }
+}
+
+fn finish_replay(
+    mut commands: Commands,
+    player: Res<ReplayPlayer>,
+    destroyed_enemy_count: Res<DestroyedEnemyCount>,
+    mut input_source: ResMut<InputSource>,
+) {
+    stop_replay(
+        &mut commands,
+        &player,
+        destroyed_enemy_count.0,
+        &mut input_source,
+    );
+}
+
+fn stop_replay(
+    commands: &mut Commands,
+    player: &ReplayPlayer,
+    destroyed_enemy_count: u32,
+    input_source: &mut InputSource,
+) {
+    let outcome = if player.tick == player.replay.ticks.len()
+        && destroyed_enemy_count == player.replay.destroyed_enemy_count
+    {
+        ReplayOutcome::Reproduced
+    } else {
+        ReplayOutcome::Diverged {
+            ticks: player.tick,
+            destroyed_enemy_count,
+            expected_ticks: player.replay.ticks.len(),
+            expected_destroyed_enemy_count: player.replay.destroyed_enemy_count,
+        }
+    };
+    match outcome {
+        ReplayOutcome::Reproduced => info!(
+            "Replay finished after {} ticks with {} enemies destroyed",
+            player.tick, destroyed_enemy_count
+        ),
+        ReplayOutcome::Diverged { .. } => error!("Stopped playback: {outcome}"),
+    }
+    *input_source = InputSource::Live;
+    commands.insert_resource(outcome);
+    commands.remove_resource::<ReplayPlayer>();
+}
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+use rand::{Rng as _, SeedableRng};
+use rand_pcg::Pcg32;
+use serde::{Deserialize, Serialize};
+
+/// Independent random number streams, one per subsystem.
+///
+/// Drawing numbers from one stream never shifts the numbers produced by another,
+/// so e.g. adding randomness to a new system doesn't change where enemies spawn.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum RngStream {
+    EnemySpawn,
+    Bot,
+    EnemyBehavior,
+}
+
+impl RngStream {
+    const ALL: [RngStream; 3] = [
+        RngStream::EnemySpawn,
+        RngStream::Bot,
+        RngStream::EnemyBehavior,
+    ];
+}
+
+/// Seedable source of randomness shared by every gameplay system.
+///
+/// All streams are re-seeded at the start of every game. With a fixed seed every
+/// game sees the exact same sequence of numbers, otherwise a new seed is drawn
+/// from entropy for each game.
+#[derive(Resource, Clone, Debug, Serialize, Deserialize)]
+pub struct GameRng {
+    fixed_seed: Option<u64>,
+    seed: u64,
+    streams: [Pcg32; RngStream::ALL.len()],
+}
+
+impl GameRng {
+    pub fn new(fixed_seed: Option<u64>) -> Self {
+        let seed = Self::draw_seed(fixed_seed);
+        Self {
+            fixed_seed,
+            seed,
+            streams: Self::seeded_streams(seed),
+        }
+    }
+
+    /// Seed used by the current game
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// Re-seed every stream for a new game
+    pub fn restart(&mut self) {
+        self.seed = Self::draw_seed(self.fixed_seed);
+        self.streams = Self::seeded_streams(self.seed);
+    }
+
+    fn draw_seed(fixed_seed: Option<u64>) -> u64 {
+        fixed_seed.unwrap_or_else(|| Pcg32::from_entropy().gen())
+    }
+
+    fn seeded_streams(seed: u64) -> [Pcg32; RngStream::ALL.len()] {
+        RngStream::ALL.map(|stream| Pcg32::new(seed, stream as u64))
+    }
+
+    pub fn stream(&mut self, stream: RngStream) -> &mut Pcg32 {
+        &mut self.streams[stream as usize]
+    }
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        Self::new(None)
+    }
+}
+
+pub fn restart_rng(mut rng: ResMut<GameRng>) {
+    rng.restart();
+}
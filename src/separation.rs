@@ -0,0 +1,70 @@
+use bevy::prelude::*;
+
+use crate::{spatial_hash::SpatialHash, CollisionShape};
+
+/// How strongly enemies steer away from each other.
+///
+/// Two enemies closer than `spacing` times their average size push each other
+/// away, harder the closer they are, so bigger enemies keep more room around
+/// them. At a `strength` of 1 an enemy right next to another is pushed away as
+/// hard as it is pulled toward the player, and at 0 enemies ignore each other.
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct EnemySeparation {
+    pub spacing: f32,
+    pub strength: f32,
+}
+
+impl Default for EnemySeparation {
+    fn default() -> Self {
+        Self {
+            spacing: 1.5,
+            strength: 1.5,
+        }
+    }
+}
+
+impl EnemySeparation {
+    /// Push on the enemy `entity` with `shape` at `position` from its
+    /// neighbours in `enemies`
+    pub(crate) fn push(
+        &self,
+        entity: Entity,
+        position: Vec2,
+        shape: &CollisionShape,
+        enemies: &SpatialHash,
+    ) -> Vec2 {
+        if self.strength <= 0. || self.spacing <= 0. {
+            return Vec2::ZERO;
+        }
+        let half_size = shape.half_extents().max_element();
+        // Lookups are already widened by the neighbours' half extents, this
+        // covers the rest of the room the biggest of them keeps
+        let largest_half_size = enemies.max_half_extents().max_element();
+        let neighbourhood = CollisionShape::Circle {
+            radius: self.spacing * half_size + (self.spacing - 1.).max(0.) * largest_half_size,
+        };
+        enemies
+            .query(position, &neighbourhood)
+            .filter(|&(other, _, _)| other != entity)
+            .filter_map(|(other, other_position, other_shape)| {
+                let radius = self.spacing * (half_size + other_shape.half_extents().max_element());
+                let offset = position - other_position;
+                let distance = offset.length();
+                if distance >= radius {
+                    return None;
+                }
+                // Enemies on the exact same spot still need to split up, in
+                // opposite directions
+                let away = if distance > 0. {
+                    offset / distance
+                } else if entity < other {
+                    Vec2::X
+                } else {
+                    Vec2::NEG_X
+                };
+                Some(away * (1. - distance / radius))
+            })
+            .sum::<Vec2>()
+            * self.strength
+    }
+}
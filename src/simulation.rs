@@ -0,0 +1,274 @@
+use bevy::{
+    ecs::{event::EventUpdateSignal, system::SystemState},
+    prelude::*,
+    time::TimeUpdateStrategy,
+};
+
+#[cfg(feature = "mobile")]
+use bevy::input::touch::{TouchInput, TouchPhase};
+
+use crate::{
+    enemy_bundle, observation::Observer, ActiveBot, AppState, Bot, Bullet, DestroyedEnemyCount,
+    Enemy, EnemyBullet, EnemyKind, EnemyTable, GamePlugin, InputSource, Observation, Player,
+    PlayerInput, RunStats, Score, Snapshot,
+};
+
+/// A headless game that is advanced one tick at a time, for tests and tools.
+///
+/// Every call to [`Simulation::tick`] runs exactly one gameplay tick, however
+/// long it takes in real time. Input can be given as key presses (or touches
+/// with the `mobile` feature), which go through the same path as live input,
+/// or directly as a [`PlayerInput`].
+pub struct Simulation {
+    app: App,
+}
+
+impl Simulation {
+    /// A seeded game, advanced until the player has spawned
+    pub fn new(seed: u64) -> Self {
+        Self::with_app(seed, |_| {})
+    }
+
+    /// Like [`Simulation::new`], letting `configure` add to the app first
+    pub fn with_app(seed: u64, configure: impl FnOnce(&mut App)) -> Self {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            GamePlugin {
+                headless: true,
+                seed: Some(seed),
+                ..default()
+            },
+        ));
+        configure(&mut app);
+
+        // Every update runs exactly one fixed tick, so events can be cleared
+        // every update. The signal meant to clear them after fixed ticks is
+        // shared by every event type, and only lets the first one through.
+        app.world.remove_resource::<EventUpdateSignal>();
+
+        let mut simulation = Self { app };
+        // The first update only runs the startup systems, which may still
+        // change the tick length, e.g. to the one of a replay
+        simulation.app.update();
+        let timestep = simulation.world().resource::<Time<Fixed>>().timestep();
+        simulation
+            .app
+            .insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+        while simulation.state() != AppState::InGame {
+            simulation.tick();
+        }
+        simulation
+    }
+
+    pub fn app(&self) -> &App {
+        &self.app
+    }
+
+    pub fn app_mut(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    pub fn world(&self) -> &World {
+        &self.app.world
+    }
+
+    pub fn world_mut(&mut self) -> &mut World {
+        &mut self.app.world
+    }
+
+    pub fn tick(&mut self) {
+        self.app.update();
+    }
+
+    pub fn advance(&mut self, ticks: u32) {
+        for _ in 0..ticks {
+            self.tick();
+        }
+    }
+
+    /// Ticks until `condition` holds, giving up after `max_ticks`. Returns
+    /// whether the condition was met.
+    pub fn advance_until(
+        &mut self,
+        max_ticks: u32,
+        mut condition: impl FnMut(&mut Self) -> bool,
+    ) -> bool {
+        for _ in 0..max_ticks {
+            if condition(self) {
+                return true;
+            }
+            self.tick();
+        }
+        condition(self)
+    }
+
+    pub fn press(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().press(key);
+    }
+
+    pub fn release(&mut self, key: KeyCode) {
+        self.app.world.resource_mut::<Input<KeyCode>>().release(key);
+    }
+
+    pub fn release_all(&mut self) {
+        self.app
+            .world
+            .resource_mut::<Input<KeyCode>>()
+            .release_all();
+    }
+
+    /// Puts finger `id` down, or moves it, at `position` in window coordinates
+    #[cfg(feature = "mobile")]
+    pub fn touch(&mut self, id: u64, position: Vec2) {
+        let phase = if self
+            .app
+            .world
+            .resource::<Touches>()
+            .get_pressed(id)
+            .is_some()
+        {
+            TouchPhase::Moved
+        } else {
+            TouchPhase::Started
+        };
+        self.send_touch(id, position, phase);
+    }
+
+    #[cfg(feature = "mobile")]
+    pub fn lift(&mut self, id: u64) {
+        if let Some(position) = self
+            .app
+            .world
+            .resource::<Touches>()
+            .get_pressed(id)
+            .map(|touch| touch.position())
+        {
+            self.send_touch(id, position, TouchPhase::Ended);
+        }
+    }
+
+    #[cfg(feature = "mobile")]
+    fn send_touch(&mut self, id: u64, position: Vec2, phase: TouchPhase) {
+        self.app.world.send_event(TouchInput {
+            phase,
+            position,
+            force: None,
+            id,
+        });
+    }
+
+    /// Bypasses keys and touches, the game uses `input` until it is set again
+    pub fn set_input(&mut self, input: PlayerInput) {
+        *self.app.world.resource_mut::<InputSource>() = InputSource::External;
+        *self.app.world.resource_mut::<PlayerInput>() = input;
+    }
+
+    /// Lets `bot` play from now on
+    pub fn set_bot(&mut self, bot: Box<dyn Bot>) {
+        self.app.world.insert_resource(ActiveBot(bot));
+        *self.app.world.resource_mut::<InputSource>() = InputSource::Bot;
+    }
+
+    /// Spawns an enemy of the first kind in the [`EnemyTable`]
+    pub fn spawn_enemy(&mut self, position: Vec2) -> Entity {
+        let kind = self.app.world.resource::<EnemyTable>().kinds[0].clone();
+        self.spawn_enemy_of_kind(position, &kind)
+    }
+
+    pub fn spawn_enemy_of_kind(&mut self, position: Vec2, kind: &EnemyKind) -> Entity {
+        self.app
+            .world
+            .spawn(enemy_bundle(position.x, position.y, kind))
+            .id()
+    }
+
+    pub fn state(&self) -> AppState {
+        self.app.world.resource::<State<AppState>>().get().clone()
+    }
+
+    pub fn destroyed_enemy_count(&self) -> u32 {
+        self.app.world.resource::<DestroyedEnemyCount>().0
+    }
+
+    pub fn score(&self) -> u32 {
+        self.app.world.resource::<Score>().0
+    }
+
+    pub fn observe(&mut self) -> Observation {
+        SystemState::<Observer>::new(&mut self.app.world)
+            .get(&self.app.world)
+            .observation()
+    }
+
+    pub fn run_stats(&self) -> &RunStats {
+        self.app.world.resource::<RunStats>()
+    }
+
+    /// Events of type `E` sent during the last tick
+    pub fn events<E: Event + Clone>(&self) -> Vec<E> {
+        self.app
+            .world
+            .resource::<Events<E>>()
+            .iter_current_update_events()
+            .cloned()
+            .collect()
+    }
+
+    pub fn snapshot(&mut self) -> Snapshot {
+        Snapshot::capture(&mut self.app.world)
+    }
+
+    /// Continues from `snapshot` in place of the current game
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        snapshot.restore(&mut self.app.world);
+    }
+
+    pub fn player_transform(&mut self) -> Option<Transform> {
+        self.app
+            .world
+            .query_filtered::<&Transform, With<Player>>()
+            .get_single(&self.app.world)
+            .ok()
+            .copied()
+    }
+
+    pub fn enemy_transforms(&mut self) -> Vec<Transform> {
+        self.app
+            .world
+            .query_filtered::<&Transform, With<Enemy>>()
+            .iter(&self.app.world)
+            .copied()
+            .collect()
+    }
+
+    pub fn bullet_transforms(&mut self) -> Vec<Transform> {
+        self.app
+            .world
+            .query_filtered::<&Transform, With<Bullet>>()
+            .iter(&self.app.world)
+            .copied()
+            .collect()
+    }
+
+    pub fn enemy_bullet_transforms(&mut self) -> Vec<Transform> {
+        self.app
+            .world
+            .query_filtered::<&Transform, With<EnemyBullet>>()
+            .iter(&self.app.world)
+            .copied()
+            .collect()
+    }
+
+    pub fn enemy_count(&mut self) -> usize {
+        self.enemy_transforms().len()
+    }
+
+    pub fn bullet_count(&mut self) -> usize {
+        self.bullet_transforms().len()
+    }
+
+    pub fn enemy_bullet_count(&mut self) -> usize {
+        self.enemy_bullet_transforms().len()
+    }
+}
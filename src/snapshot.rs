@@ -0,0 +1,423 @@
+use std::{error::Error, fmt, io, path::Path, path::PathBuf};
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    bullet_bundle, enemy_bullet_bundle, enemy_bundle, player_bundle, wall_scoreboard::cover_bundle,
+    AppState, BehaviorState, Boss, Bullet, CollisionShape, Cover, DestroyedEnemyCount, Direction,
+    Enemy, EnemyBullet, EnemyGun, EnemyKind, GameRng, Health, Player, RemainingBounces,
+    RestartTimer, RunStats, Scatter, Score, ScoreChangedEvent, WaveDirector,
+};
+#[cfg(feature = "mobile")]
+use crate::{joystick_bundles, LeftJoyStick};
+
+/// Version of the snapshot file format written by this build
+pub const SNAPSHOT_VERSION: u32 = 12;
+
+/// The gameplay state at the end of a tick.
+///
+/// Restoring it into another app, even a freshly started one, continues the
+/// game from where it was captured, tick for tick. Only the time accumulated
+/// towards the next tick is not kept, so the restored game picks up on a tick
+/// boundary.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    pub state: AppState,
+    /// Missing while no game is running
+    pub player: Option<[f32; 3]>,
+    pub enemies: Vec<EnemySnapshot>,
+    pub bullets: Vec<BulletSnapshot>,
+    pub enemy_bullets: Vec<EnemyBulletSnapshot>,
+    /// Cover left standing
+    pub covers: Vec<CoverSnapshot>,
+    pub destroyed_enemy_count: u32,
+    pub score: u32,
+    pub waves: WaveDirector,
+    pub rng: GameRng,
+    pub run_stats: RunStats,
+    /// Seconds the game has been over for, counting towards the automatic
+    /// restart
+    pub restart_timer: f32,
+}
+
+/// Position, heading and state of an enemy
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnemySnapshot {
+    pub translation: [f32; 3],
+    pub direction: Direction,
+    pub kind: EnemyKind,
+    /// Health left
+    pub hit_points: u32,
+    pub max_hit_points: u32,
+    pub gun: EnemyGun,
+    /// What its behavior remembers, see [`BehaviorState`]
+    pub behavior_timer: f32,
+    pub behavior_heading: [f32; 2],
+    pub behavior_phase: u32,
+    /// Set for the boss being fought
+    pub boss: Option<Boss>,
+    /// Seconds left flying apart after breaking off a bigger enemy
+    pub scatter: f32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct BulletSnapshot {
+    pub translation: [f32; 3],
+    pub direction: Direction,
+    /// Wall hits left before the bullet is destroyed
+    pub bounces: u32,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct EnemyBulletSnapshot {
+    pub translation: [f32; 3],
+    pub direction: Direction,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CoverSnapshot {
+    pub center: [f32; 2],
+    pub size: [f32; 2],
+    pub hit_points: u32,
+    pub max_hit_points: u32,
+}
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    Format(serde_json::Error),
+    Version(u32),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "could not access snapshot file: {err}"),
+            SnapshotError::Format(err) => write!(f, "invalid snapshot file: {err}"),
+            SnapshotError::Version(version) => write!(
+                f,
+                "snapshot file version {version} is not supported (expected {SNAPSHOT_VERSION})"
+            ),
+        }
+    }
+}
+
+impl Error for SnapshotError {}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+impl From<serde_json::Error> for SnapshotError {
+    fn from(err: serde_json::Error) -> Self {
+        SnapshotError::Format(err)
+    }
+}
+
+#[derive(Deserialize)]
+struct SnapshotHeader {
+    version: u32,
+}
+
+type EnemyState = (
+    &'static Transform,
+    &'static Direction,
+    &'static EnemyKind,
+    &'static Health,
+    &'static EnemyGun,
+    &'static BehaviorState,
+    Option<&'static Boss>,
+    Option<&'static Scatter>,
+);
+
+fn enemy_snapshots(world: &mut World) -> Vec<EnemySnapshot> {
+    world
+        .query_filtered::<EnemyState, With<Enemy>>()
+        .iter(world)
+        .map(
+            |(transform, direction, kind, health, gun, behavior_state, boss, scatter)| {
+                EnemySnapshot {
+                    translation: transform.translation.to_array(),
+                    direction: direction.clone(),
+                    kind: kind.clone(),
+                    hit_points: health.current,
+                    max_hit_points: health.max,
+                    gun: gun.clone(),
+                    behavior_timer: behavior_state.timer,
+                    behavior_heading: behavior_state.heading.to_array(),
+                    behavior_phase: behavior_state.phase,
+                    boss: boss.cloned(),
+                    scatter: scatter.map_or(0., |scatter| scatter.0),
+                }
+            },
+        )
+        .collect()
+}
+
+fn despawn_all<T: Component>(world: &mut World) {
+    let entities: Vec<Entity> = world
+        .query_filtered::<Entity, With<T>>()
+        .iter(world)
+        .collect();
+    for entity in entities {
+        world.despawn(entity);
+    }
+}
+
+impl Snapshot {
+    pub fn capture(world: &mut World) -> Self {
+        let player = world
+            .query_filtered::<&Transform, With<Player>>()
+            .get_single(world)
+            .ok()
+            .map(|transform| transform.translation.to_array());
+        Self {
+            version: SNAPSHOT_VERSION,
+            state: world.resource::<State<AppState>>().get().clone(),
+            player,
+            enemies: enemy_snapshots(world),
+            bullets: world
+                .query_filtered::<(&Transform, &Direction, &RemainingBounces), With<Bullet>>()
+                .iter(world)
+                .map(|(transform, direction, bounces)| BulletSnapshot {
+                    translation: transform.translation.to_array(),
+                    direction: direction.clone(),
+                    bounces: bounces.0,
+                })
+                .collect(),
+            enemy_bullets: world
+                .query_filtered::<(&Transform, &Direction), With<EnemyBullet>>()
+                .iter(world)
+                .map(|(transform, direction)| EnemyBulletSnapshot {
+                    translation: transform.translation.to_array(),
+                    direction: direction.clone(),
+                })
+                .collect(),
+            covers: world
+                .query::<(&Transform, &CollisionShape, &Cover)>()
+                .iter(world)
+                .map(|(transform, shape, cover)| CoverSnapshot {
+                    center: transform.translation.truncate().to_array(),
+                    size: (shape.half_extents() * 2.).to_array(),
+                    hit_points: cover.hit_points,
+                    max_hit_points: cover.max_hit_points,
+                })
+                .collect(),
+            destroyed_enemy_count: world.resource::<DestroyedEnemyCount>().0,
+            score: world.resource::<Score>().0,
+            waves: world.resource::<WaveDirector>().clone(),
+            rng: world.resource::<GameRng>().clone(),
+            run_stats: world.resource::<RunStats>().clone(),
+            restart_timer: world.resource::<RestartTimer>().0,
+        }
+    }
+
+    /// Replaces the gameplay state of `world` with this one
+    pub fn restore(&self, world: &mut World) {
+        despawn_all::<Player>(world);
+        despawn_all::<Enemy>(world);
+        despawn_all::<Bullet>(world);
+        despawn_all::<EnemyBullet>(world);
+        despawn_all::<Cover>(world);
+
+        if let Some(translation) = self.player {
+            world.spawn(player_bundle(Vec3::from_array(translation)));
+
+            #[cfg(feature = "mobile")]
+            if world
+                .query_filtered::<(), With<LeftJoyStick>>()
+                .iter(world)
+                .next()
+                .is_none()
+            {
+                let (left_joystick, right_joystick) = joystick_bundles();
+                world.spawn(left_joystick);
+                world.spawn(right_joystick);
+            }
+        }
+        for enemy in &self.enemies {
+            let [x, y, _] = enemy.translation;
+            let mut entity = world.spawn(enemy_bundle(x, y, &enemy.kind));
+            entity.insert((
+                enemy.direction.clone(),
+                Health {
+                    current: enemy.hit_points,
+                    max: enemy.max_hit_points,
+                },
+                enemy.gun.clone(),
+                BehaviorState {
+                    timer: enemy.behavior_timer,
+                    heading: Vec2::from_array(enemy.behavior_heading),
+                    phase: enemy.behavior_phase,
+                },
+            ));
+            if let Some(boss) = &enemy.boss {
+                entity.insert(boss.clone());
+            }
+            if enemy.scatter > 0. {
+                entity.insert(Scatter(enemy.scatter));
+            }
+        }
+        for bullet in &self.bullets {
+            world.spawn(bullet_bundle(
+                Vec3::from_array(bullet.translation),
+                bullet.direction.clone(),
+                bullet.bounces,
+            ));
+        }
+        for bullet in &self.enemy_bullets {
+            world.spawn(enemy_bullet_bundle(
+                Vec3::from_array(bullet.translation),
+                bullet.direction.clone(),
+            ));
+        }
+
+        for cover in &self.covers {
+            world.spawn(cover_bundle(
+                Vec2::from_array(cover.center),
+                Vec2::from_array(cover.size),
+                Cover {
+                    hit_points: cover.hit_points,
+                    max_hit_points: cover.max_hit_points,
+                },
+            ));
+        }
+
+        let previous = world.resource::<Score>().0;
+        if previous != self.score {
+            world.send_event(ScoreChangedEvent {
+                previous,
+                score: self.score,
+            });
+        }
+        world.resource_mut::<DestroyedEnemyCount>().0 = self.destroyed_enemy_count;
+        world.resource_mut::<Score>().0 = self.score;
+        world.insert_resource(self.waves.clone());
+        world.insert_resource(self.rng.clone());
+        world.insert_resource(self.run_stats.clone());
+        world.insert_resource(RestartTimer(self.restart_timer));
+        // Set directly, so that no `OnEnter` systems run and e.g. re-seed the
+        // random numbers
+        world.insert_resource(State::new(self.state.clone()));
+        world.insert_resource(NextState::<AppState>(None));
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, SnapshotError> {
+        let contents = read_storage(path.as_ref())?;
+        let header: SnapshotHeader = serde_json::from_str(&contents)?;
+        if header.version != SNAPSHOT_VERSION {
+            return Err(SnapshotError::Version(header.version));
+        }
+        Ok(serde_json::from_str(&contents)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), SnapshotError> {
+        write_storage(path.as_ref(), &serde_json::to_string(self)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn read_storage(path: &Path) -> io::Result<String> {
+    std::fs::read_to_string(path)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn write_storage(path: &Path, contents: &str) -> io::Result<()> {
+    std::fs::write(path, contents)
+}
+
+// The web has no file system, so snapshots are kept in the browser's local
+// storage instead, under their path as the key
+#[cfg(target_arch = "wasm32")]
+fn local_storage() -> io::Result<web_sys::Storage> {
+    web_sys::window()
+        .and_then(|window| window.local_storage().ok().flatten())
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, "no local storage"))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn read_storage(path: &Path) -> io::Result<String> {
+    local_storage()?
+        .get_item(&path.to_string_lossy())
+        .map_err(|err| io::Error::other(format!("{err:?}")))?
+        .ok_or_else(|| io::Error::from(io::ErrorKind::NotFound))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn write_storage(path: &Path, contents: &str) -> io::Result<()> {
+    local_storage()?
+        .set_item(&path.to_string_lossy(), contents)
+        .map_err(|err| io::Error::other(format!("{err:?}")))
+}
+
+/// Saves the game to a snapshot file at `path` when F5 is pressed, and
+/// restores it from there when F9 is pressed.
+///
+/// On the web, where there is no file system, the snapshot is kept in the
+/// browser's local storage under `path` instead.
+pub struct SnapshotPlugin {
+    pub path: PathBuf,
+}
+
+#[derive(Resource)]
+struct SnapshotPath(PathBuf);
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(SnapshotPath(self.path.clone()))
+            .add_systems(Update, (save_snapshot_on_key, load_snapshot_on_key));
+    }
+}
+
+fn save_snapshot_on_key(world: &mut World) {
+    if !world.resource::<Input<KeyCode>>().just_pressed(KeyCode::F5) {
+        return;
+    }
+    let path = world.resource::<SnapshotPath>().0.clone();
+    match Snapshot::capture(world).save(&path) {
+        Ok(()) => info!("Saved snapshot to {}", path.display()),
+        Err(err) => error!("Failed to save snapshot to {}: {err}", path.display()),
+    }
+}
+
+fn load_snapshot_on_key(world: &mut World) {
+    if !world.resource::<Input<KeyCode>>().just_pressed(KeyCode::F9) {
+        return;
+    }
+    let path = world.resource::<SnapshotPath>().0.clone();
+    match Snapshot::load(&path) {
+        Ok(snapshot) => {
+            snapshot.restore(world);
+            info!("Restored snapshot from {}", path.display());
+        }
+        Err(err) => error!("Failed to restore snapshot from {}: {err}", path.display()),
+    }
+}
+
+/// Starts the app from `snapshot` instead of a new game
+pub struct RestorePlugin {
+    pub snapshot: Snapshot,
+}
+
+#[derive(Resource)]
+struct PendingSnapshot(Snapshot);
+
+impl Plugin for RestorePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(PendingSnapshot(self.snapshot.clone()))
+            .add_systems(PostStartup, restore_pending_snapshot);
+    }
+}
+
+// Runs after the startup systems, which would otherwise set up a new game on
+// top of the restored one
+fn restore_pending_snapshot(world: &mut World) {
+    if let Some(PendingSnapshot(snapshot)) = world.remove_resource::<PendingSnapshot>() {
+        snapshot.restore(world);
+    }
+}
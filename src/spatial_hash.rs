@@ -0,0 +1,98 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{CollisionShape, Enemy};
+
+/// Side of the square cells the arena is divided into for collision checks
+pub const COLLISION_CELL_SIZE: f32 = 64.;
+
+/// Uniform grid bucketing entities by the cell their center falls in.
+///
+/// Looking up what is near a box only visits the few cells around it, so the
+/// cost of a collision check depends on how crowded that spot is rather than on
+/// how many entities there are in total.
+pub struct SpatialHash {
+    cell_size: f32,
+    cells: HashMap<IVec2, Vec<(Entity, Vec2, CollisionShape)>>,
+    // Entities are only stored in one cell, so lookups are widened by the
+    // largest half extents inserted to find the ones poking in from neighbours
+    max_half_extents: Vec2,
+}
+
+impl SpatialHash {
+    pub fn new(cell_size: f32) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::default(),
+            max_half_extents: Vec2::ZERO,
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> IVec2 {
+        (position / self.cell_size).floor().as_ivec2()
+    }
+
+    /// Largest half extents of the shapes inserted since the grid was emptied
+    pub fn max_half_extents(&self) -> Vec2 {
+        self.max_half_extents
+    }
+
+    /// Empties the grid, keeping its allocations for the next tick
+    pub fn clear(&mut self) {
+        for entities in self.cells.values_mut() {
+            entities.clear();
+        }
+        self.max_half_extents = Vec2::ZERO;
+    }
+
+    pub fn insert(&mut self, entity: Entity, position: Vec2, shape: CollisionShape) {
+        self.max_half_extents = self.max_half_extents.max(shape.half_extents());
+        let cell = self.cell(position);
+        self.cells
+            .entry(cell)
+            .or_default()
+            .push((entity, position, shape));
+    }
+
+    /// Entities that may overlap `shape` at `position`, with their position and
+    /// shape
+    pub fn query(
+        &self,
+        position: Vec2,
+        shape: &CollisionShape,
+    ) -> impl Iterator<Item = (Entity, Vec2, CollisionShape)> + '_ {
+        let half_extents = shape.half_extents() + self.max_half_extents;
+        let min = self.cell(position - half_extents);
+        let max = self.cell(position + half_extents);
+        (min.x..=max.x).flat_map(move |x| {
+            (min.y..=max.y).flat_map(move |y| {
+                self.cells
+                    .get(&IVec2::new(x, y))
+                    .into_iter()
+                    .flatten()
+                    .copied()
+            })
+        })
+    }
+}
+
+/// Enemies as they stand at the start of the tick, before anything moves
+#[derive(Resource)]
+pub struct EnemySpatialHash(pub SpatialHash);
+
+impl Default for EnemySpatialHash {
+    fn default() -> Self {
+        Self(SpatialHash::new(COLLISION_CELL_SIZE))
+    }
+}
+
+pub fn index_enemies(
+    mut enemy_spatial_hash: ResMut<EnemySpatialHash>,
+    enemy_query: Query<(Entity, &Transform, &CollisionShape), With<Enemy>>,
+) {
+    enemy_spatial_hash.0.clear();
+    for (entity, transform, shape) in enemy_query.iter() {
+        enemy_spatial_hash
+            .0
+            .insert(entity, transform.translation.truncate(), *shape);
+    }
+}
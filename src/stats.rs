@@ -0,0 +1,83 @@
+use std::fmt;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{BulletFiredEvent, Enemy, EnemyHitEvent, EnemyKilledEvent, PlayerDiedEvent};
+
+/// Statistics about the current game, reset whenever a new one starts
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunStats {
+    /// Seconds of game time the player has been alive for
+    pub survival_time: f32,
+    pub kills: u32,
+    pub shots_fired: u32,
+    /// Shots that hit an enemy, whether or not they killed it
+    pub hits: u32,
+    pub max_concurrent_enemies: usize,
+    /// How the game ended, once it has
+    pub death: Option<Death>,
+}
+
+impl RunStats {
+    /// Share of the shots fired that hit an enemy
+    pub fn accuracy(&self) -> f32 {
+        if self.shots_fired == 0 {
+            0.
+        } else {
+            self.hits as f32 / self.shots_fired as f32
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Death {
+    pub cause: DeathCause,
+    pub position: [f32; 2],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathCause {
+    EnemyContact,
+    EnemyBullet,
+}
+
+impl fmt::Display for DeathCause {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DeathCause::EnemyContact => write!(f, "enemy_contact"),
+            DeathCause::EnemyBullet => write!(f, "enemy_bullet"),
+        }
+    }
+}
+
+pub fn reset_run_stats(mut run_stats: ResMut<RunStats>) {
+    *run_stats = RunStats::default();
+}
+
+// Runs at the end of every in-game tick
+pub fn track_run_stats(
+    mut run_stats: ResMut<RunStats>,
+    enemy_query: Query<(), With<Enemy>>,
+    mut enemy_hit_events: EventReader<EnemyHitEvent>,
+    mut enemy_killed_events: EventReader<EnemyKilledEvent>,
+    mut bullet_fired_events: EventReader<BulletFiredEvent>,
+    mut player_died_events: EventReader<PlayerDiedEvent>,
+    time: Res<Time>,
+) {
+    run_stats.survival_time += time.delta_seconds();
+    run_stats.kills += enemy_killed_events.read().count() as u32;
+    run_stats.shots_fired += bullet_fired_events.read().count() as u32;
+    run_stats.hits += enemy_hit_events.read().count() as u32;
+    run_stats.max_concurrent_enemies = run_stats
+        .max_concurrent_enemies
+        .max(enemy_query.iter().count());
+
+    if let Some(player_died) = player_died_events.read().last() {
+        run_stats.death = Some(Death {
+            cause: player_died.cause,
+            position: player_died.position.to_array(),
+        });
+    }
+}
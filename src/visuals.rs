@@ -0,0 +1,256 @@
+use bevy::{
+    prelude::*,
+    sprite::{Anchor, Mesh2dHandle},
+    utils::HashMap,
+};
+
+use crate::{
+    Boss, Bullet, Enemy, EnemyBullet, EnemyKind, Health, LeftJoyStick, Player, RightJoyStick,
+};
+
+const HIT_FLASH_SECONDS: f32 = 0.08;
+const HEALTH_BAR_HEIGHT: f32 = 3.;
+// Between the top of an enemy and its health bar
+const HEALTH_BAR_GAP: f32 = 4.;
+
+// Meshes and materials are shared by every entity of the same kind, so
+// spawning a bullet doesn't allocate new assets
+#[derive(Resource)]
+pub struct GameMeshes {
+    quad: Mesh2dHandle,
+    circle: Mesh2dHandle,
+    player: Handle<ColorMaterial>,
+    // One per color of enemy kind, added as they first appear
+    enemies: HashMap<[u32; 3], Handle<ColorMaterial>>,
+    bullet: Handle<ColorMaterial>,
+    enemy_bullet: Handle<ColorMaterial>,
+    joystick: Handle<ColorMaterial>,
+    hit_flash: Handle<ColorMaterial>,
+}
+
+pub fn setup_meshes(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    commands.insert_resource(GameMeshes {
+        quad: meshes.add(Mesh::from(shape::Quad::default())).into(),
+        circle: meshes.add(shape::Circle::default().into()).into(),
+        player: materials.add(ColorMaterial::from(Color::LIME_GREEN)),
+        enemies: HashMap::default(),
+        bullet: materials.add(ColorMaterial::from(Color::RED)),
+        enemy_bullet: materials.add(ColorMaterial::from(Color::ORANGE)),
+        joystick: materials.add(ColorMaterial::from(Color::BLACK)),
+        hit_flash: materials.add(ColorMaterial::from(Color::WHITE)),
+    });
+}
+
+fn insert_mesh(
+    commands: &mut Commands,
+    entity: Entity,
+    mesh: &Mesh2dHandle,
+    material: &Handle<ColorMaterial>,
+) {
+    commands
+        .entity(entity)
+        .insert((mesh.clone(), material.clone()));
+}
+
+// Gameplay systems only spawn transforms and markers, the meshes used to draw
+// them are added here so that the headless simulation never touches the renderer
+#[allow(clippy::too_many_arguments)]
+pub fn attach_meshes(
+    mut commands: Commands,
+    mut game_meshes: ResMut<GameMeshes>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    player_query: Query<Entity, Added<Player>>,
+    enemy_query: Query<(Entity, &EnemyKind), Added<Enemy>>,
+    bullet_query: Query<Entity, Added<Bullet>>,
+    enemy_bullet_query: Query<Entity, Added<EnemyBullet>>,
+    left_joystick_query: Query<Entity, Added<LeftJoyStick>>,
+    right_joystick_query: Query<Entity, Added<RightJoyStick>>,
+) {
+    for entity in player_query.iter() {
+        insert_mesh(
+            &mut commands,
+            entity,
+            &game_meshes.circle,
+            &game_meshes.player,
+        );
+    }
+    for (entity, kind) in enemy_query.iter() {
+        let material = game_meshes
+            .enemies
+            .entry(kind.color.map(f32::to_bits))
+            .or_insert_with(|| materials.add(ColorMaterial::from(kind.color())))
+            .clone();
+        insert_mesh(&mut commands, entity, &game_meshes.quad, &material);
+    }
+    for entity in bullet_query.iter() {
+        insert_mesh(
+            &mut commands,
+            entity,
+            &game_meshes.quad,
+            &game_meshes.bullet,
+        );
+    }
+    for entity in enemy_bullet_query.iter() {
+        insert_mesh(
+            &mut commands,
+            entity,
+            &game_meshes.circle,
+            &game_meshes.enemy_bullet,
+        );
+    }
+    for entity in left_joystick_query
+        .iter()
+        .chain(right_joystick_query.iter())
+    {
+        insert_mesh(
+            &mut commands,
+            entity,
+            &game_meshes.circle,
+            &game_meshes.joystick,
+        );
+    }
+}
+
+/// Enemy drawn in white for a moment after being hit
+#[derive(Component)]
+pub struct HitFlash {
+    // Drawn with again once the flash is over
+    material: Handle<ColorMaterial>,
+    seconds_left: f32,
+}
+
+/// Bar above an enemy showing how much health it has left
+#[derive(Component)]
+pub struct HealthBar {
+    enemy: Entity,
+}
+
+/// Marks enemies which already have a [`HealthBar`]
+#[derive(Component)]
+pub struct WithHealthBar;
+
+type DamagedEnemy = (
+    Entity,
+    Ref<'static, Health>,
+    &'static mut Handle<ColorMaterial>,
+    Option<&'static mut HitFlash>,
+    Has<WithHealthBar>,
+    Has<Boss>,
+);
+
+// Health only changes when enemies are hit, or when they spawn
+pub fn show_enemy_damage(
+    mut commands: Commands,
+    game_meshes: Res<GameMeshes>,
+    mut query: Query<DamagedEnemy, With<Enemy>>,
+) {
+    for (entity, health, mut material, hit_flash, with_health_bar, boss) in query.iter_mut() {
+        if !health.is_changed() || health.is_added() {
+            continue;
+        }
+        match hit_flash {
+            Some(mut hit_flash) => hit_flash.seconds_left = HIT_FLASH_SECONDS,
+            None => {
+                commands.entity(entity).insert(HitFlash {
+                    material: material.clone(),
+                    seconds_left: HIT_FLASH_SECONDS,
+                });
+                *material = game_meshes.hit_flash.clone();
+            }
+        }
+        // Bosses have theirs along the top of the screen
+        if !with_health_bar && !boss && health.current < health.max {
+            commands.entity(entity).insert(WithHealthBar);
+            commands.spawn((
+                SpriteBundle {
+                    sprite: Sprite {
+                        anchor: Anchor::CenterLeft,
+                        ..default()
+                    },
+                    ..default()
+                },
+                HealthBar { enemy: entity },
+            ));
+        }
+    }
+}
+
+pub fn end_hit_flashes(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut HitFlash, &mut Handle<ColorMaterial>)>,
+    time: Res<Time>,
+) {
+    for (entity, mut hit_flash, mut material) in query.iter_mut() {
+        hit_flash.seconds_left -= time.delta_seconds();
+        if hit_flash.seconds_left <= 0. {
+            *material = hit_flash.material.clone();
+            commands.entity(entity).remove::<HitFlash>();
+        }
+    }
+}
+
+// Runs after the enemies' positions are interpolated, to keep the bars right
+// above them
+pub fn place_health_bars(
+    mut commands: Commands,
+    mut bar_query: Query<(Entity, &HealthBar, &mut GlobalTransform, &mut Sprite)>,
+    enemy_query: Query<(&GlobalTransform, &Health), Without<HealthBar>>,
+) {
+    for (entity, health_bar, mut global_transform, mut sprite) in bar_query.iter_mut() {
+        let Ok((enemy_transform, health)) = enemy_query.get(health_bar.enemy) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+        // Enemies are scaled to their size
+        let (size, _, position) = enemy_transform.to_scale_rotation_translation();
+        let health_left = health.fraction();
+        *global_transform = GlobalTransform::from(Transform {
+            translation: position + Vec3::new(-size.x / 2., size.y / 2. + HEALTH_BAR_GAP, 1.),
+            scale: Vec3::new(size.x * health_left, HEALTH_BAR_HEIGHT, 1.),
+            ..default()
+        });
+        sprite.color = Color::rgb(1. - health_left, health_left, 0.);
+    }
+}
+
+type Moving = Or<(With<Player>, With<Enemy>, With<Bullet>, With<EnemyBullet>)>;
+
+/// Translation at the start of the current gameplay tick
+#[derive(Component)]
+pub struct PreviousTranslation(Vec3);
+
+pub fn store_previous_translations(
+    mut commands: Commands,
+    mut query: Query<(&Transform, &mut PreviousTranslation)>,
+    new_query: Query<(Entity, &Transform), (Moving, Without<PreviousTranslation>)>,
+) {
+    for (transform, mut previous_translation) in query.iter_mut() {
+        previous_translation.0 = transform.translation;
+    }
+    for (entity, transform) in new_query.iter() {
+        commands
+            .entity(entity)
+            .insert(PreviousTranslation(transform.translation));
+    }
+}
+
+// Gameplay only moves things once per tick, so drawing them where the last tick
+// left them would stutter whenever the frame rate doesn't match the tick rate.
+// Instead they are drawn between their last two positions, according to how far
+// into the next tick the clock already is.
+pub fn interpolate_translations(
+    mut query: Query<(&mut GlobalTransform, &Transform, &PreviousTranslation)>,
+    fixed_time: Res<Time<Fixed>>,
+) {
+    let overstep = fixed_time.overstep_percentage();
+    for (mut global_transform, transform, previous_translation) in query.iter_mut() {
+        *global_transform = GlobalTransform::from(Transform {
+            translation: previous_translation.0.lerp(transform.translation, overstep),
+            ..*transform
+        });
+    }
+}
@@ -4,12 +4,13 @@
 // License https://github.com/bevyengine/bevy/blob/main/LICENSE-MIT
 // License https://github.com/bevyengine/bevy/blob/main/LICENSE-APACHE
 
-use bevy::prelude::*;
+use bevy::{ecs::system::SystemParam, prelude::*};
 
 #[cfg(feature = "mobile")]
 use crate::PRE_BOTTOM_WALL;
 use crate::{
-    DestroyedEnemyCount, LEFT_WALL, RIGHT_WALL, TOP_WALL, TRUE_BOTTOM_WALL, WALL_COLOR,
+    ArenaBounds, ArenaLayout, Bullet, CollisionShape, Cover, Enemy, EnemyBullet, Obstacle, Player,
+    Score, COVER_COLOR, LEFT_WALL, RIGHT_WALL, TOP_WALL, TRUE_BOTTOM_WALL, WALL_COLOR,
     WALL_THICKNESS,
 };
 
@@ -19,13 +20,31 @@ const SCOREBOARD_TEXT_PADDING: Val = Val::Px(5.0);
 const TEXT_COLOR: Color = Color::rgb(0.5, 0.5, 1.0);
 const SCORE_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
 
+/// Marks the text showing the score
 #[derive(Component)]
-struct Collider;
+pub struct Scoreboard;
+
+/// Marks entities that the player, enemies and bullets can't pass through
+#[derive(Component)]
+pub struct Collider;
+
+/// Marks the walls around the arena with the side of it they close off.
+///
+/// [`ArenaBounds`] is kept between the inner edges of these walls, so moving
+/// or replacing them moves the bounds too.
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArenaEdge {
+    Left,
+    Right,
+    Bottom,
+    Top,
+}
 
 // This bundle is a collection of the components that define a "wall" in our game
 #[derive(Bundle)]
 struct WallBundle {
     sprite_bundle: SpriteBundle,
+    shape: CollisionShape,
     collider: Collider,
 }
 
@@ -77,44 +96,157 @@ impl WallBundle {
     // This "builder method" allows us to reuse logic across our wall entities,
     // making our code easier to read and less prone to bugs when we change the logic
     fn new(location: WallLocation) -> WallBundle {
+        WallBundle::from_box(location.position(), location.size())
+    }
+
+    // A wall of any size and position, such as the pieces of an obstacle
+    fn from_box(position: Vec2, size: Vec2) -> WallBundle {
+        WallBundle::colored(position, size, WALL_COLOR)
+    }
+
+    fn colored(position: Vec2, size: Vec2, color: Color) -> WallBundle {
         WallBundle {
             sprite_bundle: SpriteBundle {
                 transform: Transform {
                     // We need to convert our Vec2 into a Vec3, by giving it a z-coordinate
                     // This is used to determine the order of our sprites
-                    translation: location.position().extend(0.0),
+                    translation: position.extend(0.0),
                     // The z-scale of 2D objects must always be 1.0,
                     // or their ordering will be affected in surprising ways.
                     // See https://github.com/bevyengine/bevy/issues/4149
-                    scale: location.size().extend(1.0),
-                    ..default()
-                },
-                sprite: Sprite {
-                    color: WALL_COLOR,
+                    scale: size.extend(1.0),
                     ..default()
                 },
+                sprite: Sprite { color, ..default() },
                 ..default()
             },
+            shape: CollisionShape::Box {
+                half_size: size / 2.,
+            },
             collider: Collider,
         }
     }
 }
 
+// Walls are never moved, so they can be read alongside queries moving
+// everything else
+type WallFilter = (
+    With<Collider>,
+    Without<Player>,
+    Without<Enemy>,
+    Without<Bullet>,
+    Without<EnemyBullet>,
+);
+
+/// Wall piece of destructible cover
+pub(crate) fn cover_bundle(position: Vec2, size: Vec2, cover: Cover) -> impl Bundle {
+    (WallBundle::colored(position, size, COVER_COLOR), cover)
+}
+
+/// Boxes of every wall, as centers and half sizes
+#[derive(SystemParam)]
+pub struct Walls<'w, 's> {
+    query: Query<'w, 's, (Entity, &'static Transform, &'static CollisionShape), WallFilter>,
+}
+
+impl<'w, 's> Walls<'w, 's> {
+    pub fn boxes(&self) -> Vec<(Vec2, Vec2)> {
+        self.boxes_by_entity()
+            .into_iter()
+            .map(|(_, wall)| wall)
+            .collect()
+    }
+
+    /// Boxes along with the entities they belong to
+    pub fn boxes_by_entity(&self) -> Vec<(Entity, (Vec2, Vec2))> {
+        self.query
+            .iter()
+            .filter_map(|(entity, transform, shape)| match *shape {
+                CollisionShape::Box { half_size } => {
+                    Some((entity, (transform.translation.truncate(), half_size)))
+                }
+                _ => None,
+            })
+            .collect()
+    }
+}
+
 // Add the game's entities to our world
-pub fn setup_walls(mut commands: Commands) {
+pub fn setup_walls(mut commands: Commands, layout: Res<ArenaLayout>) {
     // Walls
-    commands.spawn(WallBundle::new(WallLocation::Left));
-    commands.spawn(WallBundle::new(WallLocation::Right));
+    commands.spawn((WallBundle::new(WallLocation::Left), ArenaEdge::Left));
+    commands.spawn((WallBundle::new(WallLocation::Right), ArenaEdge::Right));
+    // On mobile the joysticks sit below the arena, behind the pre-bottom wall
     #[cfg(feature = "mobile")]
-    commands.spawn(WallBundle::new(WallLocation::PreBottom));
-    commands.spawn(WallBundle::new(WallLocation::TrueBottom));
-    commands.spawn(WallBundle::new(WallLocation::Top));
+    {
+        commands.spawn((WallBundle::new(WallLocation::PreBottom), ArenaEdge::Bottom));
+        commands.spawn(WallBundle::new(WallLocation::TrueBottom));
+    }
+    #[cfg(not(feature = "mobile"))]
+    commands.spawn((WallBundle::new(WallLocation::TrueBottom), ArenaEdge::Bottom));
+    commands.spawn((WallBundle::new(WallLocation::Top), ArenaEdge::Top));
+
+    // Obstacles inside the arena, except for the cover put up by `spawn_cover`
+    for obstacle in &layout.obstacles {
+        if matches!(obstacle, Obstacle::Cover { .. }) {
+            continue;
+        }
+        for (position, size) in obstacle.boxes() {
+            commands.spawn(WallBundle::from_box(position, size));
+        }
+    }
+}
+
+/// Keeps [`ArenaBounds`] between the inner edges of the walls around the arena
+pub fn update_arena_bounds(
+    walls: Query<(&Transform, &CollisionShape, &ArenaEdge)>,
+    mut arena_bounds: ResMut<ArenaBounds>,
+) {
+    if walls.is_empty() {
+        return;
+    }
+    let mut bounds = ArenaBounds {
+        min: Vec2::splat(f32::MIN),
+        max: Vec2::splat(f32::MAX),
+    };
+    for (transform, shape, edge) in &walls {
+        let CollisionShape::Box { half_size } = *shape else {
+            continue;
+        };
+        let center = transform.translation.truncate();
+        match edge {
+            ArenaEdge::Left => bounds.min.x = bounds.min.x.max(center.x + half_size.x),
+            ArenaEdge::Right => bounds.max.x = bounds.max.x.min(center.x - half_size.x),
+            ArenaEdge::Bottom => bounds.min.y = bounds.min.y.max(center.y + half_size.y),
+            ArenaEdge::Top => bounds.max.y = bounds.max.y.min(center.y - half_size.y),
+        }
+    }
+    arena_bounds.set_if_neq(bounds);
+}
+
+/// Puts the arena's cover back up at full strength for every new game,
+/// replacing whatever is left of it
+pub fn spawn_cover(
+    mut commands: Commands,
+    layout: Res<ArenaLayout>,
+    query: Query<Entity, With<Cover>>,
+) {
+    for entity in query.iter() {
+        commands.entity(entity).despawn();
+    }
+    for obstacle in &layout.obstacles {
+        if let Obstacle::Cover { hit_points, .. } = *obstacle {
+            for (position, size) in obstacle.boxes() {
+                commands.spawn(cover_bundle(position, size, Cover::new(hit_points)));
+            }
+        }
+    }
 }
 
 // Add the game's entities to our world
 pub fn setup_score_board(mut commands: Commands, asset_server: Res<AssetServer>) {
     // Scoreboard
-    commands.spawn(
+    commands.spawn((
         TextBundle::from_sections([
             TextSection::new(
                 "Score: ",
@@ -138,13 +270,11 @@ pub fn setup_score_board(mut commands: Commands, asset_server: Res<AssetServer>)
             left: SCOREBOARD_TEXT_PADDING,
             ..default()
         }),
-    );
+        Scoreboard,
+    ));
 }
 
-pub fn update_scoreboard(
-    destroyed_enemy_count: Res<DestroyedEnemyCount>,
-    mut query: Query<&mut Text>,
-) {
+pub fn update_scoreboard(score: Res<Score>, mut query: Query<&mut Text, With<Scoreboard>>) {
     let mut text = query.single_mut();
-    text.sections[1].value = destroyed_enemy_count.0.to_string();
+    text.sections[1].value = score.0.to_string();
 }
@@ -0,0 +1,460 @@
+use std::{error::Error, fmt, io};
+
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    ecs::system::SystemParam,
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    AppState, Boss, BossKind, EnemyBehaviors, SplitChildren, WaveStartedEvent,
+    MAX_NUMBER_OF_ENEMIES,
+};
+
+/// Asset the wave script is loaded from, and hot reloaded with the
+/// `hot-reload` feature
+pub const WAVE_SCRIPT_PATH: &str = "default.waves.ron";
+
+// Used until the asset is loaded, and by headless games which have no assets
+const DEFAULT_WAVE_SCRIPT: &str = include_str!("../assets/default.waves.ron");
+
+const WAVE_BANNER_FONT_SIZE: f32 = 60.0;
+const WAVE_BANNER_COLOR: Color = Color::rgb(1.0, 0.5, 0.5);
+
+/// Where the enemies of a group come in
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SpawnFrom {
+    Top,
+    Left,
+    Right,
+    /// Any of the other three, picked at random for every enemy
+    Edge,
+    /// Fixed point inside the arena
+    Point(f32, f32),
+}
+
+/// Enemies coming in one after the other during a wave
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SpawnGroup {
+    /// Name of a kind in the [`EnemyTable`](crate::EnemyTable). When missing,
+    /// or not in the table, a kind is picked by weight for every enemy.
+    #[serde(default)]
+    pub kind: Option<String>,
+    pub count: u32,
+    pub from: SpawnFrom,
+    /// Seconds into the wave the first enemy comes in
+    #[serde(default)]
+    pub delay: f32,
+    /// Seconds between enemies
+    #[serde(default)]
+    pub interval: f32,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Wave {
+    /// Seconds of break before the wave starts, while its number is shown
+    pub rest: f32,
+    pub groups: Vec<SpawnGroup>,
+    /// Name of one of the script's bosses, fought at the start of the wave
+    /// before any of its groups come in
+    #[serde(default)]
+    pub boss: Option<String>,
+}
+
+/// Waves made up once the scripted ones are over, each bigger and quicker
+/// than the last, with kinds picked by weight coming in from the edges
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct EndlessWaves {
+    /// Seconds of break before each wave
+    pub rest: f32,
+    /// Enemies in the first endless wave
+    pub enemies: u32,
+    /// Enemies added with every wave after it
+    pub extra_enemies: u32,
+    /// Seconds between enemies in the first endless wave
+    pub interval: f32,
+    /// Factor the interval is multiplied by with every wave after it
+    pub speedup: f32,
+    pub min_interval: f32,
+    /// Every how many endless waves one starts with a boss, taking turns
+    /// through the script's bosses. None when 0.
+    #[serde(default)]
+    pub boss_every: u32,
+}
+
+/// The waves of enemies making up a game, one after the other.
+///
+/// A wave is over once all of its enemies have come in and been destroyed.
+/// Enemies of a wave wait for their turn while `MAX_NUMBER_OF_ENEMIES` are
+/// already in the arena, and while a boss is being fought.
+#[derive(Asset, Resource, TypePath, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WaveScript {
+    pub waves: Vec<Wave>,
+    pub endless: EndlessWaves,
+    #[serde(default)]
+    pub bosses: Vec<BossKind>,
+}
+
+#[derive(Debug)]
+pub enum WaveScriptError {
+    Io(io::Error),
+    Format(ron::error::SpannedError),
+    Invalid(String),
+}
+
+impl fmt::Display for WaveScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WaveScriptError::Io(err) => write!(f, "could not read wave script: {err}"),
+            WaveScriptError::Format(err) => write!(f, "invalid wave script: {err}"),
+            WaveScriptError::Invalid(reason) => write!(f, "invalid wave script: {reason}"),
+        }
+    }
+}
+
+impl Error for WaveScriptError {}
+
+impl From<io::Error> for WaveScriptError {
+    fn from(err: io::Error) -> Self {
+        WaveScriptError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for WaveScriptError {
+    fn from(err: ron::error::SpannedError) -> Self {
+        WaveScriptError::Format(err)
+    }
+}
+
+impl WaveScript {
+    pub fn from_ron(ron: &str) -> Result<Self, WaveScriptError> {
+        let script: Self = ron::from_str(ron)?;
+        script.validate()?;
+        Ok(script)
+    }
+
+    fn validate(&self) -> Result<(), WaveScriptError> {
+        let invalid = |reason: String| Err(WaveScriptError::Invalid(reason));
+        for (index, wave) in self.waves.iter().enumerate() {
+            let number = index + 1;
+            if wave.rest < 0. {
+                return invalid(format!("wave {number} has a negative rest"));
+            }
+            if wave.boss.is_none() && wave.groups.iter().all(|group| group.count == 0) {
+                return invalid(format!("wave {number} has no enemies"));
+            }
+            if let Some(boss) = &wave.boss {
+                if self.find_boss(boss).is_none() {
+                    return invalid(format!("wave {number} has unknown boss {boss:?}"));
+                }
+            }
+            if wave
+                .groups
+                .iter()
+                .any(|group| group.delay < 0. || group.interval < 0.)
+            {
+                return invalid(format!("wave {number} has a negative delay or interval"));
+            }
+        }
+        let endless = &self.endless;
+        if endless.rest < 0. || endless.enemies == 0 {
+            return invalid("endless waves need enemies and a rest of at least 0".into());
+        }
+        if endless.min_interval <= 0. || endless.interval < endless.min_interval {
+            return invalid("endless waves need an interval of at least min_interval".into());
+        }
+        if endless.speedup <= 0. || endless.speedup > 1. {
+            return invalid("endless waves need a speedup between 0 and 1".into());
+        }
+        if endless.boss_every > 0 && self.bosses.is_empty() {
+            return invalid("endless waves need bosses to have boss waves".into());
+        }
+        if let Some(problem) = self.bosses.iter().find_map(BossKind::problem) {
+            return invalid(problem);
+        }
+        Ok(())
+    }
+
+    fn find_boss(&self, name: &str) -> Option<&BossKind> {
+        self.bosses.iter().find(|boss| boss.name == name)
+    }
+
+    /// Scripted wave `wave`, counting from 1, if the script goes that far
+    fn scripted(&self, wave: u32) -> Option<&Wave> {
+        self.waves.get(wave.checked_sub(1)? as usize)
+    }
+
+    /// Boss fought at the start of wave `wave`, counting from 1, if any
+    pub fn boss(&self, wave: u32) -> Option<&BossKind> {
+        match self.scripted(wave) {
+            Some(scripted) => self.find_boss(scripted.boss.as_deref()?),
+            None => {
+                let every = self.endless.boss_every;
+                let endless_wave = wave.checked_sub(self.waves.len() as u32)?;
+                if every == 0 || endless_wave == 0 || !endless_wave.is_multiple_of(every) {
+                    return None;
+                }
+                let turn = (endless_wave / every - 1) as usize;
+                self.bosses.get(turn.checked_rem(self.bosses.len())?)
+            }
+        }
+    }
+
+    /// Seconds of break before wave `wave`, counting from 1
+    pub fn rest(&self, wave: u32) -> f32 {
+        match self.scripted(wave) {
+            Some(scripted) => scripted.rest,
+            None => self.endless.rest,
+        }
+    }
+
+    /// Enemies of wave `wave`, counting from 1, by the time they come in
+    pub fn spawns(&self, wave: u32) -> Vec<PendingSpawn> {
+        let mut spawns: Vec<PendingSpawn> = match self.scripted(wave) {
+            Some(scripted) => scripted
+                .groups
+                .iter()
+                .flat_map(|group| {
+                    (0..group.count).map(|index| PendingSpawn {
+                        time: group.delay + index as f32 * group.interval,
+                        kind: group.kind.clone(),
+                        from: group.from,
+                    })
+                })
+                .collect(),
+            None => {
+                let endless = &self.endless;
+                let later = wave.saturating_sub(self.waves.len() as u32 + 1);
+                let interval = (endless.interval * endless.speedup.powi(later as i32))
+                    .max(endless.min_interval);
+                (0..endless.enemies + later * endless.extra_enemies)
+                    .map(|index| PendingSpawn {
+                        time: index as f32 * interval,
+                        kind: None,
+                        from: SpawnFrom::Edge,
+                    })
+                    .collect()
+            }
+        };
+        // Stable, so that groups listed first come in first on ties
+        spawns.sort_by(|a, b| a.time.total_cmp(&b.time));
+        spawns
+    }
+}
+
+impl Default for WaveScript {
+    fn default() -> Self {
+        Self::from_ron(DEFAULT_WAVE_SCRIPT).expect("the default wave script is valid")
+    }
+}
+
+/// Enemy of the current wave waiting to come in
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PendingSpawn {
+    /// Seconds into the wave
+    pub time: f32,
+    pub kind: Option<String>,
+    pub from: SpawnFrom,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum WaveStage {
+    /// Seconds into the break before the wave
+    Break(f32),
+    /// Seconds into the wave, and the enemies still to come, soonest first
+    Running(f32, Vec<PendingSpawn>),
+}
+
+/// Where the game is in the [`WaveScript`]
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WaveDirector {
+    /// The current wave, or the next one during a break, counting from 1
+    pub wave: u32,
+    pub stage: WaveStage,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            wave: 1,
+            stage: WaveStage::Break(0.),
+        }
+    }
+}
+
+impl WaveDirector {
+    pub fn is_between_waves(&self) -> bool {
+        matches!(self.stage, WaveStage::Break(_))
+    }
+
+    /// Moves `delta` seconds on, with `alive` enemies in the arena and room
+    /// for `room` more. Returns the enemies coming in now, and whether a new
+    /// wave started.
+    pub(crate) fn advance(
+        &mut self,
+        script: &WaveScript,
+        delta: f32,
+        alive: usize,
+        room: usize,
+    ) -> (Vec<PendingSpawn>, bool) {
+        match &mut self.stage {
+            WaveStage::Break(elapsed) => {
+                *elapsed += delta;
+                if *elapsed < script.rest(self.wave) {
+                    return (Vec::new(), false);
+                }
+                self.stage = WaveStage::Running(0., script.spawns(self.wave));
+                (Vec::new(), true)
+            }
+            WaveStage::Running(elapsed, pending) => {
+                if pending.is_empty() && alive == 0 {
+                    self.wave += 1;
+                    self.stage = WaveStage::Break(0.);
+                    return (Vec::new(), false);
+                }
+                *elapsed += delta;
+                let due = pending
+                    .iter()
+                    .take(room)
+                    .take_while(|spawn| spawn.time <= *elapsed)
+                    .count();
+                (pending.drain(..due).collect(), false)
+            }
+        }
+    }
+}
+
+/// The wave script along with where the game is in it
+#[derive(SystemParam)]
+pub struct Waves<'w, 's> {
+    script: Res<'w, WaveScript>,
+    director: ResMut<'w, WaveDirector>,
+    bosses: Query<'w, 's, (), With<Boss>>,
+    split_children: Res<'w, SplitChildren>,
+    wave_started_event: EventWriter<'w, WaveStartedEvent>,
+}
+
+impl<'w, 's> Waves<'w, 's> {
+    /// Same as [`WaveDirector::advance`] with `alive` enemies in the arena,
+    /// besides the ones split off this tick, and room for the rest of
+    /// `MAX_NUMBER_OF_ENEMIES`. Sends a [`WaveStartedEvent`] when a wave
+    /// starts, and also returns the boss to fight when the wave starts with
+    /// one. Stands still while a boss is around.
+    pub(crate) fn advance(
+        &mut self,
+        delta: f32,
+        alive: usize,
+    ) -> (Vec<PendingSpawn>, Option<BossKind>) {
+        if !self.bosses.is_empty() {
+            return (Vec::new(), None);
+        }
+        let alive = alive + self.split_children.0;
+        let room = MAX_NUMBER_OF_ENEMIES.saturating_sub(alive);
+        let (spawns, started) = self.director.advance(&self.script, delta, alive, room);
+        if !started {
+            return (spawns, None);
+        }
+        self.wave_started_event.send(WaveStartedEvent {
+            wave: self.director.wave,
+        });
+        (spawns, self.script.boss(self.director.wave).cloned())
+    }
+}
+
+#[derive(Default)]
+pub struct WaveScriptLoader;
+
+impl AssetLoader for WaveScriptLoader {
+    type Asset = WaveScript;
+    type Settings = ();
+    type Error = WaveScriptError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<WaveScript, WaveScriptError>> {
+        Box::pin(async move {
+            let mut ron = String::new();
+            reader.read_to_string(&mut ron).await?;
+            WaveScript::from_ron(&ron)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["waves.ron"]
+    }
+}
+
+#[derive(Resource)]
+pub struct WaveScriptHandle(Handle<WaveScript>);
+
+pub fn load_wave_script(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(WaveScriptHandle(asset_server.load(WAVE_SCRIPT_PATH)));
+}
+
+/// Switches to the script from the asset once it is loaded, and whenever the
+/// file changes. The game in progress goes on from the wave it is at.
+pub fn apply_wave_script(
+    mut asset_events: EventReader<AssetEvent<WaveScript>>,
+    handle: Res<WaveScriptHandle>,
+    scripts: Res<Assets<WaveScript>>,
+    behaviors: Res<EnemyBehaviors>,
+    mut wave_script: ResMut<WaveScript>,
+) {
+    for event in asset_events.read() {
+        if event.is_loaded_with_dependencies(&handle.0) || event.is_modified(&handle.0) {
+            if let Some(script) = scripts.get(&handle.0) {
+                if let Err(err) = behaviors.check_bosses(script) {
+                    error!("Kept the previous wave script: {err}");
+                    continue;
+                }
+                *wave_script = script.clone();
+                info!("Loaded {} scripted waves", wave_script.waves.len());
+            }
+        }
+    }
+}
+
+/// Text announcing the next wave, shown during the break before it
+#[derive(Component)]
+pub struct WaveBanner;
+
+pub fn setup_wave_banner(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/RusticBlackShadow.ttf"),
+                font_size: WAVE_BANNER_FONT_SIZE,
+                color: WAVE_BANNER_COLOR,
+            },
+        )
+        .with_text_alignment(TextAlignment::Center)
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Percent(35.),
+            width: Val::Percent(100.),
+            justify_content: JustifyContent::Center,
+            ..default()
+        }),
+        WaveBanner,
+    ));
+}
+
+pub fn update_wave_banner(
+    wave_director: Res<WaveDirector>,
+    app_state: Res<State<AppState>>,
+    mut query: Query<(&mut Text, &mut Visibility), With<WaveBanner>>,
+) {
+    let (mut text, mut visibility) = query.single_mut();
+    if *app_state.get() == AppState::InGame && wave_director.is_between_waves() {
+        text.sections[0].value = format!("Wave {}", wave_director.wave);
+        *visibility = Visibility::Inherited;
+    } else {
+        *visibility = Visibility::Hidden;
+    }
+}
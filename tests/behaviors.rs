@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    Behavior, BehaviorState, Dash, EnemyBehaviors, EnemyKind, EnemyTable, Flee, Intercept, Orbit,
+    Simulation, Steering, Wander, Zigzag,
+};
+use rand::SeedableRng;
+use rand_pcg::Pcg32;
+
+const DELTA: f32 = 1. / 60.;
+
+// An enemy right above the player, with nothing in between
+fn steering() -> Steering {
+    Steering {
+        position: Vec2::new(0., 100.),
+        player: Vec2::ZERO,
+        player_velocity: Vec2::ZERO,
+        path: Vec2::new(0., -1.),
+        in_sight: true,
+        speed: 70.,
+        health: 1.,
+        delta: DELTA,
+    }
+}
+
+// Headings over `ticks` ticks
+fn steer(behavior: &dyn Behavior, steering: &Steering, ticks: usize) -> Vec<Vec2> {
+    let mut state = BehaviorState::default();
+    let mut rng = Pcg32::seed_from_u64(1);
+    (0..ticks)
+        .map(|_| behavior.steer(steering, &mut state, &mut rng))
+        .collect()
+}
+
+#[test]
+fn zigzag_weaves_around_the_path() {
+    let headings = steer(&Zigzag::default(), &steering(), 120);
+    assert!(headings.iter().any(|heading| heading.x > 0.3));
+    assert!(headings.iter().any(|heading| heading.x < -0.3));
+    assert!(headings.iter().all(|heading| heading.y < 0.));
+}
+
+#[test]
+fn zigzag_needs_a_period() {
+    let mut behaviors = EnemyBehaviors::default();
+    assert!(behaviors.check(&EnemyTable::default()).is_ok());
+    behaviors.register(
+        "zigzag",
+        Zigzag {
+            period: 0.,
+            ..default()
+        },
+    );
+    assert!(behaviors.check(&EnemyTable::default()).is_err());
+}
+
+#[test]
+fn orbit_circles_at_its_radius() {
+    let orbit = Orbit { radius: 100. };
+    let heading = steer(&orbit, &steering(), 1)[0];
+    // Around the player rather than towards it
+    assert!(heading.x.abs() > 0.9);
+
+    let far = Steering {
+        position: Vec2::new(0., 300.),
+        ..steering()
+    };
+    assert!(steer(&orbit, &far, 1)[0].y < -0.5);
+    let hidden = Steering {
+        in_sight: false,
+        path: Vec2::X,
+        ..steering()
+    };
+    assert_eq!(steer(&orbit, &hidden, 1)[0], Vec2::X);
+}
+
+#[test]
+fn dash_charges_then_pauses() {
+    let dash = Dash {
+        charge: 0.5,
+        pause: 0.5,
+        pace: 3.,
+    };
+    let headings = steer(&dash, &steering(), 60);
+    assert_eq!(headings[0], Vec2::new(0., -3.));
+    assert_eq!(headings[20], Vec2::new(0., -3.));
+    assert_eq!(headings[40], Vec2::ZERO);
+
+    // Still pauses when charging nowhere, right on top of the player
+    let on_top = Steering {
+        path: Vec2::ZERO,
+        ..steering()
+    };
+    let mut state = BehaviorState::default();
+    let mut rng = Pcg32::seed_from_u64(1);
+    dash.steer(&on_top, &mut state, &mut rng);
+    for _ in 0..40 {
+        dash.steer(&steering(), &mut state, &mut rng);
+    }
+    assert_eq!(dash.steer(&steering(), &mut state, &mut rng), Vec2::ZERO);
+}
+
+#[test]
+fn wander_roams_at_its_own_pace() {
+    let wander = Wander::default();
+    let headings = steer(&wander, &steering(), 120);
+    assert!(headings
+        .iter()
+        .all(|heading| (heading.length() - wander.pace).abs() < 1e-4));
+    // Reproducible for the same random numbers
+    assert_eq!(headings, steer(&wander, &steering(), 120));
+}
+
+#[test]
+fn flee_runs_away_when_hurt() {
+    let flee = Flee { below: 0.5 };
+    assert_eq!(steer(&flee, &steering(), 1)[0], Vec2::new(0., -1.));
+    let hurt = Steering {
+        health: 0.25,
+        ..steering()
+    };
+    assert_eq!(steer(&flee, &hurt, 1)[0], Vec2::new(0., 1.));
+}
+
+#[test]
+fn intercept_leads_a_moving_player() {
+    let moving = Steering {
+        player_velocity: Vec2::new(100., 0.),
+        ..steering()
+    };
+    let heading = steer(&Intercept::default(), &moving, 1)[0];
+    assert!(heading.x > 0.5);
+    assert!(heading.y < 0.);
+}
+
+struct Sidestep;
+
+impl Behavior for Sidestep {
+    fn steer(&self, _steering: &Steering, _state: &mut BehaviorState, _rng: &mut Pcg32) -> Vec2 {
+        Vec2::X
+    }
+}
+
+#[test]
+fn apps_can_add_behaviors() {
+    let mut simulation = Simulation::with_app(1, |app| {
+        let mut behaviors = EnemyBehaviors::default();
+        behaviors.register("sidestep", Sidestep);
+        app.insert_resource(behaviors);
+    });
+    let player = simulation.player_transform().unwrap().translation;
+    let start = Vec2::new(player.x - 100., player.y + 100.);
+    let enemy = simulation.spawn_enemy_of_kind(
+        start,
+        &EnemyKind {
+            behavior: "sidestep".into(),
+            ..EnemyTable::default().kinds[0].clone()
+        },
+    );
+
+    simulation.advance(60);
+    let position = simulation
+        .world()
+        .get::<Transform>(enemy)
+        .unwrap()
+        .translation;
+    assert!(position.x > start.x + 30.);
+    assert_eq!(position.y, start.y);
+}
@@ -0,0 +1,206 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    Boss, BossDefeatedEvent, BossKind, BossPhase, Chase, Direction, EndlessWaves, EnemyBehaviors,
+    EnemyFire, EnemyKind, EnemySpawnedEvent, FirePattern, PlayerInput, Simulation, SpawnFrom,
+    SpawnGroup, Wave, WaveScript, WaveScriptError,
+};
+
+fn phase(from: f32, behavior: &str, speed: f32) -> BossPhase {
+    BossPhase {
+        from,
+        behavior: behavior.into(),
+        speed,
+        ..default()
+    }
+}
+
+// Stands still, so that it is easy to hit
+fn boss(name: &str) -> BossKind {
+    BossKind {
+        name: name.into(),
+        hit_points: 4,
+        score: 20,
+        phases: vec![phase(1., "chase", 0.), phase(0.5, "orbit", 0.)],
+        ..default()
+    }
+}
+
+// With a boss every other endless wave
+fn script(waves: Vec<Wave>, bosses: Vec<BossKind>) -> WaveScript {
+    let script = common::script(waves);
+    WaveScript {
+        endless: EndlessWaves {
+            boss_every: 2,
+            ..script.endless
+        },
+        bosses,
+        ..script
+    }
+}
+
+fn boss_wave(boss: &str, groups: Vec<SpawnGroup>) -> Wave {
+    Wave {
+        groups,
+        boss: Some(boss.into()),
+        ..default()
+    }
+}
+
+#[test]
+fn phases_follow_health() {
+    let warden = BossKind {
+        phases: vec![
+            phase(1., "chase", 1.),
+            phase(0.6, "wander", 2.),
+            phase(0.25, "dash", 3.),
+        ],
+        ..boss("warden")
+    };
+    assert_eq!(warden.phase_at(1.), 0);
+    assert_eq!(warden.phase_at(0.7), 0);
+    assert_eq!(warden.phase_at(0.6), 1);
+    assert_eq!(warden.phase_at(0.3), 1);
+    assert_eq!(warden.phase_at(0.1), 2);
+    let kind = warden.kind(2);
+    assert_eq!(kind.behavior, "dash");
+    assert_eq!(kind.speed, 3.);
+    assert_eq!(kind.hit_points, warden.hit_points);
+}
+
+#[test]
+fn invalid_bosses_are_rejected() {
+    let ron = |script: &WaveScript| ron::to_string(script).unwrap();
+    let mut script = script(vec![boss_wave("warden", Vec::new())], vec![boss("warden")]);
+    assert_eq!(WaveScript::from_ron(&ron(&script)).unwrap(), script);
+
+    script.waves[0].boss = Some("nobody".into());
+    assert!(matches!(
+        WaveScript::from_ron(&ron(&script)),
+        Err(WaveScriptError::Invalid(_))
+    ));
+
+    script.waves[0].boss = Some("warden".into());
+    script.bosses[0].phases[1].from = 1.;
+    assert!(matches!(
+        WaveScript::from_ron(&ron(&script)),
+        Err(WaveScriptError::Invalid(_))
+    ));
+
+    script.bosses[0].phases[1].from = 0.5;
+    script.bosses[0].phases[1].fire = Some(EnemyFire {
+        pattern: FirePattern::Radial { bullets: 0 },
+        cooldown: 1.,
+    });
+    assert!(matches!(
+        WaveScript::from_ron(&ron(&script)),
+        Err(WaveScriptError::Invalid(_))
+    ));
+}
+
+#[test]
+fn bosses_with_unknown_behaviors_are_rejected() {
+    let mut script = script(vec![boss_wave("warden", Vec::new())], vec![boss("warden")]);
+    let mut behaviors = EnemyBehaviors::default();
+    assert!(behaviors.check_bosses(&script).is_ok());
+
+    script.bosses[0].phases[1].behavior = "teleport".into();
+    assert!(matches!(
+        behaviors.check_bosses(&script),
+        Err(WaveScriptError::Invalid(_))
+    ));
+    behaviors.register("teleport", Chase);
+    assert!(behaviors.check_bosses(&script).is_ok());
+    assert!(behaviors.check_bosses(&WaveScript::default()).is_ok());
+}
+
+#[test]
+fn endless_waves_take_turns_with_bosses() {
+    let script = script(
+        vec![boss_wave("warden", Vec::new())],
+        vec![boss("warden"), boss("colossus")],
+    );
+    let names: Vec<Option<&str>> = (1..=6)
+        .map(|wave| script.boss(wave).map(|boss| boss.name.as_str()))
+        .collect();
+    assert_eq!(
+        names,
+        [
+            Some("warden"),
+            None,
+            Some("warden"),
+            None,
+            Some("colossus"),
+            None
+        ]
+    );
+}
+
+#[test]
+fn bosses_are_fought_alone_and_change_phases() {
+    let grunts = SpawnGroup {
+        kind: Some("grunt".into()),
+        count: 2,
+        from: SpawnFrom::Top,
+        delay: 0.,
+        interval: 0.,
+    };
+    let mut simulation = Simulation::with_app(1, |app| {
+        app.insert_resource(script(
+            vec![boss_wave("warden", vec![grunts])],
+            vec![boss("warden")],
+        ));
+    });
+
+    let mut spawned = Vec::new();
+    simulation.advance_until(10, |simulation| {
+        spawned.extend(simulation.events::<EnemySpawnedEvent>());
+        !spawned.is_empty()
+    });
+    assert_eq!(spawned.len(), 1);
+    assert_eq!(spawned[0].kind, "warden");
+    let boss = spawned[0].enemy;
+
+    // The grunts wait for the boss to be defeated
+    simulation.advance(60);
+    assert_eq!(simulation.enemy_count(), 1);
+
+    // Right in front of the player, clear of any cover
+    let player = simulation.player_transform().unwrap().translation;
+    simulation
+        .world_mut()
+        .get_mut::<Transform>(boss)
+        .unwrap()
+        .translation = Vec3::new(player.x, player.y + 120., 0.);
+    simulation.set_input(PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    });
+
+    let mut defeated = Vec::new();
+    let mut orbiting = false;
+    simulation.advance_until(300, |simulation| {
+        if let Some(kind) = simulation.world().get::<EnemyKind>(boss) {
+            orbiting |= kind.behavior == "orbit"
+                && simulation.world().get::<Boss>(boss).unwrap().phase == 1;
+        }
+        defeated.extend(simulation.events::<BossDefeatedEvent>());
+        !defeated.is_empty()
+    });
+    assert!(orbiting);
+    assert_eq!(defeated.len(), 1);
+    assert_eq!(defeated[0].boss, boss);
+    assert_eq!(defeated[0].score_delta, 20);
+    assert_eq!(simulation.score(), 20);
+    // The bonus only adds to the score, enemies speed up as for any other kill
+    assert_eq!(simulation.destroyed_enemy_count(), 1);
+
+    simulation.set_input(PlayerInput::default());
+    let mut grunts = 0;
+    simulation.advance_until(10, |simulation| {
+        grunts += simulation.events::<EnemySpawnedEvent>().len();
+        grunts == 2
+    });
+    assert_eq!(grunts, 2);
+}
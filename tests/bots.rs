@@ -0,0 +1,52 @@
+use bevy_shoot_em_up::{bot_by_name, AppState, Simulation, BOT_NAMES};
+
+// Lets the bot play for up to `max_ticks`, returning the score and how many
+// ticks it survived
+fn play(bot: &str, seed: u64, max_ticks: u32) -> (u32, u32) {
+    let mut simulation = Simulation::new(seed);
+    simulation.set_bot(bot_by_name(bot).unwrap());
+    let mut ticks = 0;
+    simulation.advance_until(max_ticks, |simulation| {
+        ticks += 1;
+        simulation.state() == AppState::GameOver
+    });
+    (simulation.score(), ticks)
+}
+
+#[test]
+fn every_bot_plays_reproducible_games() {
+    for bot in BOT_NAMES {
+        assert_eq!(play(bot, 5, 1200), play(bot, 5, 1200), "{bot} bot");
+    }
+}
+
+#[test]
+fn kite_bot_outscores_random_bot() {
+    let score = |bot| (0..3).map(|seed| play(bot, seed, 1800).0).sum::<u32>();
+    assert!(score("kite") > score("random"));
+}
+
+#[test]
+fn unknown_bot_is_rejected() {
+    assert!(bot_by_name("clairvoyant").is_none());
+}
+
+#[test]
+fn run_stats_describe_the_game() {
+    let mut simulation = Simulation::new(3);
+    simulation.set_bot(bot_by_name("random").unwrap());
+    simulation.advance_until(3600, |simulation| simulation.state() == AppState::GameOver);
+
+    let stats = simulation.run_stats();
+    // Every kind of enemy is worth at least a point
+    assert_eq!(stats.kills, simulation.destroyed_enemy_count());
+    assert!(stats.kills <= simulation.score());
+    assert!(stats.shots_fired >= stats.hits && stats.hits >= stats.kills);
+    assert!(stats.accuracy() <= 1.);
+    assert!(stats.survival_time > 0.);
+    assert!(stats.max_concurrent_enemies > 0);
+    assert_eq!(
+        stats.death.is_some(),
+        simulation.state() == AppState::GameOver
+    );
+}
@@ -0,0 +1,81 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{AppState, CollisionShape, Direction, PlayerInput, Simulation};
+
+const CIRCLE: CollisionShape = CollisionShape::Circle { radius: 10. };
+const SQUARE: CollisionShape = CollisionShape::Box {
+    half_size: Vec2::splat(10.),
+};
+const CAPSULE: CollisionShape = CollisionShape::Capsule {
+    half_segment: Vec2::new(20., 0.),
+    radius: 5.,
+};
+
+#[test]
+fn every_pair_of_shapes_collides_by_its_outline() {
+    let cases = [
+        (CIRCLE, CIRCLE, Vec2::new(19., 0.), Vec2::new(15., 15.)),
+        (CIRCLE, SQUARE, Vec2::new(19., 0.), Vec2::new(18., 18.)),
+        (SQUARE, SQUARE, Vec2::new(19., 19.), Vec2::new(21., 0.)),
+        (CAPSULE, CIRCLE, Vec2::new(34., 0.), Vec2::new(0., 16.)),
+        (CAPSULE, SQUARE, Vec2::new(34., 0.), Vec2::new(36., 14.)),
+        (CAPSULE, CAPSULE, Vec2::new(0., 9.), Vec2::new(0., 11.)),
+    ];
+    for (shape, other, touching, apart) in cases {
+        for (a, b) in [(shape, other), (other, shape)] {
+            assert!(a.intersects(Vec2::ZERO, &b, touching), "{a:?} {b:?}");
+            assert!(!a.intersects(Vec2::ZERO, &b, apart), "{a:?} {b:?}");
+        }
+    }
+}
+
+#[test]
+fn capsule_crossing_a_box_collides() {
+    let long_capsule = CollisionShape::Capsule {
+        half_segment: Vec2::new(0., 100.),
+        radius: 1.,
+    };
+    assert!(long_capsule.intersects(Vec2::ZERO, &SQUARE, Vec2::new(0., 50.)));
+    assert!(!long_capsule.intersects(Vec2::ZERO, &SQUARE, Vec2::new(15., 50.)));
+}
+
+#[test]
+fn enemy_in_corner_of_player_bounding_box_is_harmless() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    simulation.spawn_enemy(player.truncate() + Vec2::new(22., 22.));
+
+    simulation.tick();
+    assert_eq!(simulation.state(), AppState::InGame);
+
+    assert!(simulation.advance_until(60, |simulation| simulation.state() == AppState::GameOver));
+}
+
+#[test]
+fn fast_bullets_hit_enemies_they_fly_past() {
+    // At 5 ticks per second a bullet moves six times the width of an enemy in
+    // one tick
+    let mut simulation = Simulation::with_app(1, |app| {
+        app.insert_resource(Time::<Fixed>::from_hz(5.));
+    });
+    let player = simulation.player_transform().unwrap().translation;
+    simulation.spawn_enemy(Vec2::new(player.x, player.y + 200.));
+
+    simulation.set_input(PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    });
+    simulation.tick();
+    simulation.set_input(PlayerInput::default());
+
+    assert!(simulation.advance_until(5, |simulation| simulation.destroyed_enemy_count() == 1));
+    assert_eq!(simulation.state(), AppState::InGame);
+}
+
+#[test]
+fn swept_shape_covers_the_whole_path() {
+    let (center, swept) = SQUARE.swept(Vec2::ZERO, Vec2::new(0., 100.));
+    assert_eq!(center, Vec2::new(0., 50.));
+    assert!(swept.intersects(center, &CIRCLE, Vec2::new(0., 50.)));
+    assert!(!swept.intersects(center, &CIRCLE, Vec2::new(25., 50.)));
+    assert_eq!(SQUARE.swept(Vec2::ONE, Vec2::ONE), (Vec2::ONE, SQUARE));
+}
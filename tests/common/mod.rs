@@ -0,0 +1,18 @@
+use bevy_shoot_em_up::{EndlessWaves, Wave, WaveScript};
+
+// Scripted `waves`, followed by short and quick endless waves without bosses
+pub fn script(waves: Vec<Wave>) -> WaveScript {
+    WaveScript {
+        waves,
+        endless: EndlessWaves {
+            rest: 1.,
+            enemies: 4,
+            extra_enemies: 2,
+            interval: 1.,
+            speedup: 0.5,
+            min_interval: 0.2,
+            boss_every: 0,
+        },
+        bosses: Vec::new(),
+    }
+}
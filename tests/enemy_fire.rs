@@ -0,0 +1,113 @@
+use std::f32::consts::{FRAC_PI_2, TAU};
+
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    AppState, BulletCancel, DeathCause, Direction, EnemyFire, EnemyKind, EnemyTable, FirePattern,
+    PlayerDiedEvent, PlayerInput, Simulation,
+};
+
+// Only enemies that don't shoot come in, leaving the turrets placed by the
+// tests as the only ones firing
+fn simulation(bullet_cancel: bool) -> Simulation {
+    Simulation::with_app(1, |app| {
+        let mut enemy_table = EnemyTable::default();
+        enemy_table.kinds.retain(|kind| kind.fire.is_none());
+        app.insert_resource(enemy_table)
+            .insert_resource(BulletCancel {
+                enabled: bullet_cancel,
+            });
+    })
+}
+
+// Stays put and takes a long time to destroy
+fn turret(pattern: FirePattern, cooldown: f32) -> EnemyKind {
+    EnemyKind {
+        name: "turret".into(),
+        speed: 0.,
+        hit_points: 1000,
+        fire: Some(EnemyFire { pattern, cooldown }),
+        ..default()
+    }
+}
+
+#[test]
+fn patterns_spread_their_bullets() {
+    let aimed = FirePattern::Aimed.directions(Vec2::new(0., -50.), 0.);
+    assert_eq!(aimed, vec![Vec2::new(0., -1.)]);
+    assert!(FirePattern::Aimed.directions(Vec2::ZERO, 0.).is_empty());
+
+    let radial = FirePattern::Radial { bullets: 4 }.directions(Vec2::X, 0.);
+    assert_eq!(radial.len(), 4);
+    for (direction, angle) in radial.iter().zip([0., FRAC_PI_2, TAU / 2., 3. * FRAC_PI_2]) {
+        assert!(direction.distance(Vec2::from_angle(angle)) < 1e-5);
+    }
+
+    let spiral = FirePattern::Spiral {
+        bullets: 2,
+        turn: 0.5,
+    };
+    let turned = spiral.directions(Vec2::X, 0.5);
+    assert!(turned[0].distance(Vec2::from_angle(0.5)) < 1e-5);
+    assert!(turned[1].distance(-Vec2::from_angle(0.5)) < 1e-5);
+}
+
+#[test]
+fn enemy_bullets_kill_the_player() {
+    let mut simulation = simulation(false);
+    let player = simulation.player_transform().unwrap().translation;
+    simulation.spawn_enemy_of_kind(
+        Vec2::new(player.x, player.y + 150.),
+        &turret(FirePattern::Aimed, 0.5),
+    );
+
+    simulation.advance(20);
+    assert_eq!(simulation.enemy_bullet_count(), 0);
+    let mut died = Vec::new();
+    assert!(simulation.advance_until(120, |simulation| {
+        died.extend(simulation.events::<PlayerDiedEvent>());
+        !died.is_empty()
+    }));
+    assert_eq!(died.len(), 1);
+    assert_eq!(died[0].cause, DeathCause::EnemyBullet);
+    assert!(simulation.player_transform().is_none());
+    assert_eq!(
+        simulation.run_stats().death.as_ref().unwrap().cause,
+        DeathCause::EnemyBullet
+    );
+}
+
+#[test]
+fn radial_bursts_fire_every_cooldown() {
+    let mut simulation = simulation(false);
+    let player = simulation.player_transform().unwrap().translation;
+    simulation.spawn_enemy_of_kind(
+        Vec2::new(player.x, player.y + 300.),
+        &turret(FirePattern::Radial { bullets: 8 }, 0.25),
+    );
+
+    assert!(simulation.advance_until(30, |simulation| simulation.enemy_bullet_count() > 0));
+    assert_eq!(simulation.enemy_bullet_count(), 8);
+    simulation.advance(15);
+    assert_eq!(simulation.enemy_bullet_count(), 16);
+}
+
+fn fire_at_turret(bullet_cancel: bool) -> AppState {
+    let mut simulation = simulation(bullet_cancel);
+    let player = simulation.player_transform().unwrap().translation;
+    simulation.spawn_enemy_of_kind(
+        Vec2::new(player.x, player.y + 250.),
+        &turret(FirePattern::Aimed, 0.3),
+    );
+    simulation.set_input(PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    });
+    simulation.advance(120);
+    simulation.state()
+}
+
+#[test]
+fn bullets_only_cancel_each_other_when_enabled() {
+    assert_eq!(fire_at_turret(false), AppState::GameOver);
+    assert_eq!(fire_at_turret(true), AppState::InGame);
+}
@@ -0,0 +1,149 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    Chase, Direction, EnemyBehaviors, EnemyKind, EnemyTable, EnemyTableError, GameRng, Health,
+    PlayerInput, RngStream, Simulation, BULLET_DAMAGE,
+};
+
+fn kind(name: &str, weight: f32) -> EnemyKind {
+    EnemyKind {
+        name: name.into(),
+        weight,
+        ..default()
+    }
+}
+
+#[test]
+fn default_table_is_the_asset_file() {
+    let ron = std::fs::read_to_string("assets/default.enemies.ron").unwrap();
+    assert_eq!(EnemyTable::from_ron(&ron).unwrap(), EnemyTable::default());
+    assert!(EnemyTable::default().kinds.len() > 1);
+}
+
+#[test]
+fn invalid_tables_are_rejected() {
+    assert!(matches!(
+        EnemyTable::from_ron("(kinds: [(name: \"grunt\")])"),
+        Err(EnemyTableError::Format(_))
+    ));
+
+    let mut table = EnemyTable {
+        kinds: vec![kind("grunt", 0.)],
+    };
+    let ron = |table: &EnemyTable| ron::to_string(table).unwrap();
+    assert!(matches!(
+        EnemyTable::from_ron(&ron(&table)),
+        Err(EnemyTableError::Invalid(_))
+    ));
+
+    table.kinds[0].weight = 1.;
+    table.kinds[0].behavior = "teleport".into();
+    assert_eq!(EnemyTable::from_ron(&ron(&table)).unwrap(), table);
+    let mut behaviors = EnemyBehaviors::default();
+    assert!(matches!(
+        behaviors.check(&table),
+        Err(EnemyTableError::Invalid(_))
+    ));
+    behaviors.register("teleport", Chase);
+    assert!(behaviors.check(&table).is_ok());
+    assert!(behaviors.check(&EnemyTable::default()).is_ok());
+}
+
+#[test]
+fn tables_with_numbers_that_arent_finite_are_rejected() {
+    let ron = |table: &EnemyTable| ron::to_string(table).unwrap();
+    let breakages: [fn(&mut EnemyKind); 4] = [
+        |kind| kind.weight = f32::NAN,
+        |kind| kind.size = f32::INFINITY,
+        |kind| kind.speed = f32::NAN,
+        |kind| kind.speed_ramp = f32::NEG_INFINITY,
+    ];
+    for break_kind in breakages {
+        let mut table = EnemyTable {
+            kinds: vec![kind("grunt", 1.), kind("runner", 1.)],
+        };
+        break_kind(&mut table.kinds[1]);
+        assert!(matches!(
+            EnemyTable::from_ron(&ron(&table)),
+            Err(EnemyTableError::Invalid(_))
+        ));
+    }
+}
+
+#[test]
+fn kinds_are_picked_by_weight() {
+    let table = EnemyTable {
+        kinds: vec![kind("common", 3.), kind("never", 0.), kind("rare", 1.)],
+    };
+    let mut rng = GameRng::new(Some(1));
+    let mut common = 0;
+    for _ in 0..4000 {
+        match table.pick(rng.stream(RngStream::EnemySpawn)).name.as_str() {
+            "common" => common += 1,
+            "rare" => {}
+            name => panic!("picked {name}"),
+        }
+    }
+    assert!((2800..3200).contains(&common));
+}
+
+#[test]
+fn enemies_spawn_from_the_table() {
+    let mut simulation = Simulation::with_app(1, |app| {
+        app.insert_resource(EnemyTable {
+            kinds: vec![EnemyKind {
+                size: 12.,
+                ..kind("tiny", 1.)
+            }],
+        });
+    });
+    assert!(simulation.advance_until(60, |simulation| simulation.enemy_count() > 0));
+
+    let world = simulation.world_mut();
+    let (kind, transform) = world.query::<(&EnemyKind, &Transform)>().single(world);
+    assert_eq!(kind.name, "tiny");
+    assert_eq!(transform.scale.x, 12.);
+}
+
+#[test]
+fn tough_enemies_take_several_hits() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    let enemy = simulation.spawn_enemy_of_kind(
+        Vec2::new(player.x, player.y + 300.),
+        &EnemyKind {
+            hit_points: 3,
+            score: 5,
+            speed: 0.,
+            ..kind("tough", 1.)
+        },
+    );
+
+    simulation.set_input(PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    });
+    simulation.advance(2);
+    simulation.set_input(PlayerInput::default());
+    assert_eq!(simulation.world().get::<Health>(enemy).unwrap().current, 3);
+    simulation.advance(60);
+    assert!(simulation.world().get_entity(enemy).is_some());
+    assert_eq!(simulation.destroyed_enemy_count(), 0);
+    let health = *simulation.world().get::<Health>(enemy).unwrap();
+    assert_eq!(health.max, 3);
+    assert_eq!(health.current, 3 - 2 * BULLET_DAMAGE);
+
+    simulation.set_input(PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    });
+    simulation.tick();
+    simulation.set_input(PlayerInput::default());
+    assert!(simulation.advance_until(60, |simulation| simulation.score() == 5));
+    assert_eq!(simulation.destroyed_enemy_count(), 1);
+    assert!(simulation.world().get_entity(enemy).is_none());
+
+    // Every shot counts towards the accuracy, not just the one that killed
+    let stats = simulation.run_stats();
+    assert_eq!((stats.shots_fired, stats.hits, stats.kills), (3, 3, 1));
+    assert_eq!(stats.accuracy(), 1.);
+}
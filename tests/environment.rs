@@ -0,0 +1,102 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    Direction, Environment, InvalidAction, PlayerInput, DEATH_REWARD, KILL_REWARD,
+};
+
+#[test]
+fn reset_observes_a_fresh_game() {
+    let mut environment = Environment::new(1);
+    let observation = environment.reset(2);
+
+    assert!(observation.player.is_some());
+    assert!(observation.bullets.is_empty());
+    assert_eq!(observation.destroyed_enemy_count, 0);
+}
+
+#[test]
+fn kills_are_rewarded_and_death_ends_the_episode() {
+    let mut environment = Environment::new(1);
+    let player = environment.reset(1).player.unwrap();
+    environment
+        .simulation()
+        .spawn_enemy(Vec2::new(player[0], player[1] + 200.));
+
+    let fire = PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    };
+    let mut total_reward = 0.;
+    let mut steps = 0;
+    let last_step = loop {
+        let step = environment.step(fire.clone()).unwrap();
+        total_reward += step.reward;
+        steps += 1;
+        if step.done || steps > 10_000 {
+            break step;
+        }
+    };
+
+    assert!(last_step.done);
+    assert!(last_step.observation.player.is_none());
+    // Whatever each enemy was worth
+    let kills = environment.simulation().run_stats().kills as f32;
+    assert!(kills > 0.);
+    assert_eq!(total_reward, kills * KILL_REWARD + DEATH_REWARD);
+
+    // The episode is over until the next reset
+    assert_eq!(environment.step(fire).unwrap().reward, 0.);
+}
+
+#[test]
+fn actions_are_clamped_to_unit_length() {
+    let walk = |x: f32| {
+        let mut environment = Environment::new(1);
+        environment.reset(1);
+        let action = PlayerInput {
+            movement: Direction { x, y: 0. },
+            ..default()
+        };
+        for _ in 0..10 {
+            environment.step(action.clone()).unwrap();
+        }
+        environment
+            .step(action)
+            .unwrap()
+            .observation
+            .player
+            .unwrap()
+    };
+    let start = Environment::new(1).reset(1).player.unwrap();
+    let walked = walk(1.);
+    assert!(walked[0] > start[0]);
+    assert_eq!(walk(100.), walked);
+}
+
+#[test]
+fn non_finite_actions_are_rejected_without_stepping() {
+    let steps = |environment: &mut Environment| {
+        (0..10)
+            .map(|_| {
+                environment
+                    .step(PlayerInput::default())
+                    .unwrap()
+                    .observation
+            })
+            .collect::<Vec<_>>()
+    };
+    let mut environment = Environment::new(1);
+    environment.reset(1);
+    let action = PlayerInput {
+        movement: Direction {
+            x: f32::NAN,
+            y: f32::INFINITY,
+        },
+        ..default()
+    };
+    assert!(matches!(environment.step(action), Err(InvalidAction(_))));
+
+    // The game went on as if the action had never been sent
+    let mut untouched = Environment::new(1);
+    untouched.reset(1);
+    assert_eq!(steps(&mut environment), steps(&mut untouched));
+}
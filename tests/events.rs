@@ -0,0 +1,62 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    BulletFiredEvent, DeathCause, Direction, EnemyKilledEvent, EnemySpawnedEvent, PlayerDiedEvent,
+    PlayerInput, ScoreChangedEvent, Simulation,
+};
+
+#[test]
+fn firing_and_killing_are_reported() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    let enemy = simulation.spawn_enemy(Vec2::new(player.x, player.y + 200.));
+
+    simulation.set_input(PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    });
+    simulation.tick();
+    let fired = simulation.events::<BulletFiredEvent>();
+    assert_eq!(fired.len(), 1);
+    assert_eq!(fired[0].direction, Direction { x: 0., y: 1. });
+    assert!(fired[0].origin.y > player.y);
+
+    simulation.set_input(PlayerInput::default());
+    let mut killed = Vec::new();
+    let mut score_changes = Vec::new();
+    simulation.advance_until(60, |simulation| {
+        killed.extend(simulation.events::<EnemyKilledEvent>());
+        score_changes.extend(simulation.events::<ScoreChangedEvent>());
+        !killed.is_empty()
+    });
+    assert_eq!(killed.len(), 1);
+    assert_eq!(killed[0].enemy, enemy);
+    assert_eq!(killed[0].bullet, fired[0].bullet);
+    assert_eq!(killed[0].score_delta, 1);
+    assert_eq!(score_changes.len(), 1);
+    assert_eq!((score_changes[0].previous, score_changes[0].score), (0, 1));
+}
+
+#[test]
+fn spawns_and_death_are_reported() {
+    let mut simulation = Simulation::new(2);
+    let mut spawned = Vec::new();
+    simulation.advance_until(120, |simulation| {
+        spawned.extend(simulation.events::<EnemySpawnedEvent>());
+        !spawned.is_empty()
+    });
+    assert!(!spawned.is_empty());
+    assert_eq!(
+        simulation.enemy_transforms()[0].translation.truncate(),
+        spawned[0].position
+    );
+
+    let player = simulation.player_transform().unwrap().translation;
+    let killer = simulation.spawn_enemy(player.truncate());
+    simulation.tick();
+    let died = simulation.events::<PlayerDiedEvent>();
+    assert_eq!(died.len(), 1);
+    assert_eq!(died[0].killer, killer);
+    assert_eq!(died[0].cause, DeathCause::EnemyContact);
+    assert_eq!(died[0].position, player.truncate());
+    assert_eq!(died[0].survival_time, simulation.run_stats().survival_time);
+}
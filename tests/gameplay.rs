@@ -0,0 +1,313 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    AppState, Direction, EnemySeparation, EnemyTable, GamePlugin, PlayerInput, Simulation,
+    ENEMY_SIZE, PLAYER_LEFT_BOUNDARY, PLAYER_RIGHT_BOUNDARY,
+};
+
+fn fire(x: f32, y: f32) -> PlayerInput {
+    PlayerInput {
+        fire: Direction { x, y },
+        ..default()
+    }
+}
+
+fn movement(x: f32, y: f32) -> PlayerInput {
+    PlayerInput {
+        movement: Direction { x, y },
+        ..default()
+    }
+}
+
+#[test]
+fn game_starts_with_player_and_no_score() {
+    let mut simulation = Simulation::new(1);
+
+    assert_eq!(simulation.state(), AppState::InGame);
+    assert!(simulation.player_transform().is_some());
+    assert_eq!(simulation.destroyed_enemy_count(), 0);
+    assert_eq!(simulation.bullet_count(), 0);
+}
+
+#[test]
+fn bullets_fly_in_fire_direction_and_leave_the_arena() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+
+    simulation.set_input(fire(0., 1.));
+    simulation.tick();
+    assert_eq!(simulation.bullet_count(), 1);
+
+    simulation.set_input(PlayerInput::default());
+    simulation.advance(10);
+    let bullets = simulation.bullet_transforms();
+    assert_eq!(bullets.len(), 1);
+    assert_eq!(bullets[0].translation.x, player.x);
+    assert!(bullets[0].translation.y > player.y + 50.);
+
+    simulation.advance(120);
+    assert_eq!(simulation.bullet_count(), 0);
+}
+
+#[test]
+fn player_is_kept_inside_the_arena() {
+    let mut simulation = Simulation::new(1);
+
+    simulation.set_input(movement(-1., 0.));
+    simulation.advance(60);
+    assert_eq!(
+        simulation.player_transform().unwrap().translation.x,
+        PLAYER_LEFT_BOUNDARY
+    );
+
+    simulation.set_input(movement(1., 0.));
+    simulation.advance(90);
+    assert_eq!(
+        simulation.player_transform().unwrap().translation.x,
+        PLAYER_RIGHT_BOUNDARY
+    );
+}
+
+#[test]
+fn bullet_hitting_enemy_destroys_both() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    let enemy = simulation.spawn_enemy(Vec2::new(player.x, player.y + 200.));
+
+    simulation.set_input(fire(0., 1.));
+    simulation.tick();
+    simulation.set_input(PlayerInput::default());
+
+    assert!(simulation.advance_until(60, |simulation| simulation.destroyed_enemy_count() == 1));
+    assert!(simulation.world().get_entity(enemy).is_none());
+    assert_eq!(simulation.bullet_count(), 0);
+}
+
+#[test]
+fn enemy_touching_player_ends_the_game() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+
+    simulation.spawn_enemy(Vec2::new(player.x + ENEMY_SIZE, player.y));
+
+    assert!(simulation.advance_until(10, |simulation| simulation.state() == AppState::GameOver));
+    assert!(simulation.player_transform().is_none());
+}
+
+#[test]
+fn game_restarts_after_game_over() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    simulation.spawn_enemy(Vec2::new(player.x, player.y + 200.));
+    simulation.set_input(fire(0., 1.));
+    assert!(simulation.advance_until(60, |simulation| simulation.destroyed_enemy_count() == 1));
+    simulation.set_input(PlayerInput::default());
+
+    assert!(simulation.advance_until(3600, |simulation| simulation.state() == AppState::GameOver));
+    assert!(simulation.enemy_count() > 0);
+
+    // Without any key, the game restarts on its own after 3 seconds
+    assert!(simulation.advance_until(200, |simulation| simulation.state() == AppState::InGame));
+    assert!(simulation.player_transform().is_some());
+    assert_eq!(simulation.destroyed_enemy_count(), 0);
+    assert_eq!(simulation.enemy_count(), 0);
+}
+
+#[cfg(not(feature = "mobile"))]
+#[test]
+fn keyboard_moves_and_fires() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+
+    simulation.press(KeyCode::Right);
+    simulation.press(KeyCode::D);
+    simulation.advance(5);
+    assert!(simulation.player_transform().unwrap().translation.x > player.x);
+    assert_eq!(simulation.bullet_count(), 5);
+    let bullet = simulation.bullet_transforms()[0].translation;
+    assert!(bullet.x < simulation.player_transform().unwrap().translation.x);
+
+    simulation.release_all();
+    simulation.advance(5);
+    assert_eq!(simulation.bullet_count(), 5);
+}
+
+#[cfg(not(feature = "mobile"))]
+#[test]
+fn r_key_restarts_immediately() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    simulation.spawn_enemy(Vec2::new(player.x, player.y));
+    assert!(simulation.advance_until(10, |simulation| simulation.state() == AppState::GameOver));
+
+    simulation.press(KeyCode::R);
+    assert!(simulation.advance_until(5, |simulation| simulation.state() == AppState::InGame));
+}
+
+#[cfg(feature = "mobile")]
+#[test]
+fn right_joystick_moves_player() {
+    use bevy_shoot_em_up::{
+        JOYSTICK_Y, MOBILE_WINDOW_HEIGHT, MOBILE_WINDOW_WIDTH, RIGHT_JOYSTICK_X,
+    };
+
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+
+    // Window coordinates grow downwards from the top left corner
+    let touch = Vec2::new(
+        RIGHT_JOYSTICK_X + 100. + MOBILE_WINDOW_WIDTH / 2.,
+        MOBILE_WINDOW_HEIGHT / 2. - JOYSTICK_Y,
+    );
+    simulation.touch(0, touch);
+    simulation.advance(5);
+    assert!(simulation.player_transform().unwrap().translation.x > player.x);
+
+    simulation.lift(0);
+    simulation.tick();
+    let x = simulation.player_transform().unwrap().translation.x;
+    simulation.advance(5);
+    assert_eq!(simulation.player_transform().unwrap().translation.x, x);
+}
+
+#[cfg(feature = "mobile")]
+#[test]
+fn left_joystick_only_fires_inside_its_zone() {
+    use bevy_shoot_em_up::{
+        JOYSTICK_Y, JOYSTICK_ZONE_SIZE, LEFT_JOYSTICK_X, MOBILE_WINDOW_HEIGHT, MOBILE_WINDOW_WIDTH,
+    };
+
+    let mut simulation = Simulation::new(1);
+    let touch = |y: f32| {
+        Vec2::new(
+            LEFT_JOYSTICK_X + MOBILE_WINDOW_WIDTH / 2.,
+            MOBILE_WINDOW_HEIGHT / 2. - y,
+        )
+    };
+
+    // Right above the joystick, but out of its reach
+    simulation.touch(0, touch(JOYSTICK_Y + JOYSTICK_ZONE_SIZE + 50.));
+    simulation.advance(5);
+    assert_eq!(simulation.bullet_count(), 0);
+
+    simulation.touch(0, touch(JOYSTICK_Y + 100.));
+    simulation.advance(5);
+    assert!(simulation.bullet_count() > 0);
+}
+
+#[test]
+fn same_seed_and_input_play_the_same_game() {
+    let play = |seed| {
+        let mut simulation = Simulation::new(seed);
+        simulation.set_input(movement(1., 0.));
+        simulation.advance(200);
+        simulation
+            .enemy_transforms()
+            .iter()
+            .map(|transform| transform.translation)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(play(7), play(7));
+    assert_ne!(play(7), play(8));
+}
+
+#[test]
+fn bullet_destroys_only_one_of_overlapping_enemies() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    simulation.spawn_enemy(Vec2::new(player.x, player.y + 200.));
+    simulation.spawn_enemy(Vec2::new(player.x + 2., player.y + 200.));
+
+    simulation.set_input(fire(0., 1.));
+    simulation.tick();
+    simulation.set_input(PlayerInput::default());
+
+    assert!(simulation.advance_until(60, |simulation| simulation.destroyed_enemy_count() == 1));
+    simulation.tick();
+    assert_eq!(simulation.destroyed_enemy_count(), 1);
+    assert_eq!(simulation.enemy_count(), 1);
+}
+
+#[test]
+fn collisions_are_found_among_hundreds_of_enemies() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    for row in 0..15 {
+        for column in -10..10 {
+            simulation.spawn_enemy(Vec2::new(
+                column as f32 * 1.5 * ENEMY_SIZE,
+                player.y + 300. + row as f32 * 1.5 * ENEMY_SIZE,
+            ));
+        }
+    }
+
+    simulation.set_input(fire(0., 1.));
+    simulation.advance(60);
+
+    assert_eq!(simulation.state(), AppState::InGame);
+    assert!(simulation.destroyed_enemy_count() > 0);
+    assert_eq!(
+        simulation.enemy_count() as u32 + simulation.destroyed_enemy_count(),
+        300
+    );
+}
+
+fn closest_enemies_distance(simulation: &mut Simulation) -> f32 {
+    let enemies = simulation.enemy_transforms();
+    let mut closest = f32::INFINITY;
+    for (i, enemy) in enemies.iter().enumerate() {
+        for other in &enemies[i + 1..] {
+            closest = closest.min(enemy.translation.distance(other.translation));
+        }
+    }
+    closest
+}
+
+#[test]
+fn enemies_spread_out_instead_of_stacking() {
+    let spawn_stack = |simulation: &mut Simulation| {
+        for _ in 0..5 {
+            simulation.spawn_enemy(Vec2::new(0., 150.));
+        }
+    };
+
+    let mut simulation = Simulation::new(1);
+    spawn_stack(&mut simulation);
+    simulation.advance(60);
+    assert!(closest_enemies_distance(&mut simulation) > ENEMY_SIZE / 2.);
+
+    let mut simulation = Simulation::with_app(1, |app| {
+        app.insert_resource(EnemySeparation {
+            strength: 0.,
+            ..default()
+        });
+    });
+    spawn_stack(&mut simulation);
+    simulation.advance(60);
+    assert_eq!(closest_enemies_distance(&mut simulation), 0.);
+}
+
+#[test]
+fn bigger_enemies_keep_more_room_around_them() {
+    let mut simulation = Simulation::new(1);
+    let mut kind = simulation.world().resource::<EnemyTable>().kinds[0].clone();
+    kind.size = 3. * ENEMY_SIZE;
+    for _ in 0..5 {
+        simulation.spawn_enemy_of_kind(Vec2::new(0., 150.), &kind);
+    }
+    simulation.advance(60);
+    assert!(closest_enemies_distance(&mut simulation) > kind.size / 2.);
+}
+
+#[test]
+#[should_panic(expected = "tick_rate")]
+fn zero_tick_rate_is_rejected() {
+    App::new().add_plugins((
+        MinimalPlugins,
+        GamePlugin {
+            headless: true,
+            tick_rate: 0.,
+            ..default()
+        },
+    ));
+}
@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    ArenaBounds, ArenaLayout, FlowField, Obstacle, Simulation, ENEMY_SIZE, NAVIGATION_CELL_SIZE,
+};
+
+#[test]
+fn flow_field_leads_around_walls() {
+    let bounds = ArenaBounds {
+        min: Vec2::splat(-100.),
+        max: Vec2::splat(100.),
+    };
+    // A wall across most of the arena, between the start and the target
+    let walls = [(Vec2::new(-20., 0.), Vec2::new(80., 10.))];
+    let target = Vec2::new(0., -80.);
+    let mut flow_field = FlowField::default();
+    flow_field.update(&bounds, &walls, ENEMY_SIZE / 2., target);
+
+    let mut position = Vec2::new(0., 60.);
+    let mut steps = 0;
+    while let Some(direction) = flow_field.direction(position) {
+        position += direction * 5.;
+        // Around the open end of the wall, on the right
+        let (center, half_size) = walls[0];
+        let gap = (position - center).abs() - half_size;
+        assert!(gap.x > 0. || gap.y > 0.);
+        steps += 1;
+        assert!(steps < 100);
+    }
+    // Somewhere in the same cell as the target
+    assert!(position.distance(target) < NAVIGATION_CELL_SIZE * std::f32::consts::SQRT_2);
+    assert!(flow_field.distance_to_target(Vec2::new(0., 60.)).unwrap() > 140. / 20.);
+}
+
+#[test]
+fn flow_field_leaves_out_gaps_too_narrow_for_the_biggest_enemy() {
+    let bounds = ArenaBounds {
+        min: Vec2::splat(-100.),
+        max: Vec2::splat(100.),
+    };
+    // A wall across the arena, with a gap in the middle
+    let walls = [
+        (Vec2::new(-65., 0.), Vec2::new(35., 5.)),
+        (Vec2::new(65., 0.), Vec2::new(35., 5.)),
+    ];
+    let target = Vec2::new(0., -80.);
+    let start = Vec2::new(0., 60.);
+    let mut flow_field = FlowField::default();
+
+    flow_field.update(&bounds, &walls, ENEMY_SIZE / 2., target);
+    assert!(flow_field.distance_to_target(start).is_some());
+
+    flow_field.update(&bounds, &walls, 1.5 * ENEMY_SIZE, target);
+    assert_eq!(flow_field.distance_to_target(start), None);
+}
+
+#[test]
+fn enemies_find_their_way_out_of_dead_ends() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    // A cup opening away from the player, with an enemy inside
+    let cup = |x: f32, y: f32, width: f32, height: f32| Obstacle::Block {
+        center: Vec2::new(player.x + x, player.y + y),
+        size: Vec2::new(width, height),
+    };
+    let mut simulation = Simulation::with_app(1, |app| {
+        app.insert_resource(ArenaLayout {
+            obstacles: vec![
+                cup(0., 100., 200., 20.),
+                cup(-90., 160., 20., 100.),
+                cup(90., 160., 20., 100.),
+            ],
+        });
+    });
+    let enemy = simulation.spawn_enemy(Vec2::new(player.x, player.y + 140.));
+
+    assert!(simulation.advance_until(1200, |simulation| {
+        let position = simulation
+            .world()
+            .get::<Transform>(enemy)
+            .unwrap()
+            .translation;
+        position.y < player.y + 90.
+    }));
+}
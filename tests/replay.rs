@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    AppState, Direction, InputSource, PlayerInput, RecordPlugin, Replay, ReplayError,
+    ReplayOutcome, ReplayPlugin, Simulation, REPLAY_VERSION,
+};
+
+#[test]
+fn replay_reproduces_recorded_game() {
+    let path = std::env::temp_dir().join("bevy-shoot-em-up-replay-test.json");
+
+    let recording_path = path.clone();
+    let mut simulation = Simulation::with_app(3, |app| {
+        app.add_plugins(RecordPlugin {
+            path: recording_path,
+        });
+    });
+    let mut tick = 0;
+    let game_over = simulation.advance_until(10_000, |simulation| {
+        tick += 1;
+        simulation.set_input(PlayerInput {
+            movement: Direction {
+                x: if tick % 80 < 40 { -1. } else { 1. },
+                y: 0.,
+            },
+            fire: Direction {
+                x: [-1., 0., 1.][tick % 3],
+                y: 1.,
+            },
+        });
+        simulation.state() == AppState::GameOver
+    });
+    assert!(game_over);
+    let destroyed_enemy_count = simulation.destroyed_enemy_count();
+    assert!(destroyed_enemy_count > 0);
+
+    let replay = Replay::load(&path).unwrap();
+    assert_eq!(replay.destroyed_enemy_count, destroyed_enemy_count);
+    let recorded_ticks = replay.ticks.len() as u32;
+
+    // The replay brings its own seed
+    let mut playback = Simulation::with_app(4, |app| {
+        app.add_plugins(ReplayPlugin {
+            replay: replay.clone(),
+        });
+    });
+    playback.advance(recorded_ticks - 1);
+    assert_eq!(playback.state(), AppState::InGame);
+    assert!(playback.advance_until(2, |simulation| simulation.state() == AppState::GameOver));
+    assert_eq!(playback.destroyed_enemy_count(), destroyed_enemy_count);
+    assert_eq!(
+        playback.world().resource::<ReplayOutcome>(),
+        &ReplayOutcome::Reproduced
+    );
+
+    // A recording cut short runs out while the game is still going
+    let mut truncated = replay.clone();
+    truncated.ticks.truncate(recorded_ticks as usize / 2);
+    truncated.checks.truncate(recorded_ticks as usize / 2);
+    let mut playback = Simulation::with_app(4, |app| {
+        app.add_plugins(ReplayPlugin { replay: truncated });
+    });
+    playback.advance(recorded_ticks / 2 + 1);
+    assert_eq!(playback.state(), AppState::InGame);
+    assert!(matches!(
+        playback.world().resource::<ReplayOutcome>(),
+        ReplayOutcome::Diverged { ticks, .. } if *ticks == recorded_ticks as usize / 2 + 1
+    ));
+    assert_eq!(
+        *playback.world().resource::<InputSource>(),
+        InputSource::Live
+    );
+
+    // A tick that doesn't match the recording stops the playback right away
+    let mut tampered = replay;
+    tampered.checks[50].score += 1;
+    let mut playback = Simulation::with_app(4, |app| {
+        app.add_plugins(ReplayPlugin { replay: tampered });
+    });
+    assert!(
+        playback.advance_until(recorded_ticks, |simulation| simulation
+            .world()
+            .contains_resource::<ReplayOutcome>())
+    );
+    assert_eq!(playback.state(), AppState::InGame);
+    assert!(matches!(
+        playback.world().resource::<ReplayOutcome>(),
+        ReplayOutcome::Diverged { ticks: 51, .. }
+    ));
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn playback_runs_at_the_recorded_tick_rate() {
+    let timestep = Duration::from_secs(1) / 30;
+    let replay = Replay {
+        version: REPLAY_VERSION,
+        seed: 1,
+        timestep_nanos: timestep.as_nanos() as u64,
+        ticks: vec![PlayerInput::default(); 100],
+        checks: Vec::new(),
+        destroyed_enemy_count: 0,
+    };
+    let mut playback = Simulation::with_app(4, |app| {
+        app.add_plugins(ReplayPlugin { replay });
+    });
+    let elapsed = |simulation: &Simulation| simulation.world().resource::<Time<Fixed>>().elapsed();
+    let before = elapsed(&playback);
+    playback.advance(3);
+    assert_eq!(elapsed(&playback) - before, timestep * 3);
+}
+
+#[test]
+fn replay_from_other_version_is_rejected() {
+    let path = std::env::temp_dir().join("bevy-shoot-em-up-old-replay-test.json");
+    std::fs::write(&path, r#"{"version": 0}"#).unwrap();
+    assert!(matches!(Replay::load(&path), Err(ReplayError::Version(0))));
+
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,117 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    AppState, Cover, Direction, Health, PlayerInput, Simulation, Snapshot, SnapshotError,
+    SNAPSHOT_VERSION,
+};
+
+fn input(tick: u32) -> PlayerInput {
+    PlayerInput {
+        movement: Direction {
+            x: if tick % 60 < 30 { -1. } else { 1. },
+            y: 0.,
+        },
+        fire: Direction { x: 0., y: 1. },
+    }
+}
+
+fn play(simulation: &mut Simulation, ticks: std::ops::Range<u32>) {
+    for tick in ticks {
+        simulation.set_input(input(tick));
+        simulation.tick();
+    }
+}
+
+fn cover_hit_points(simulation: &mut Simulation) -> Vec<u32> {
+    let world = simulation.world_mut();
+    let mut hit_points: Vec<u32> = world
+        .query::<&Cover>()
+        .iter(world)
+        .map(|cover| cover.hit_points)
+        .collect();
+    hit_points.sort();
+    hit_points
+}
+
+#[test]
+fn restored_snapshot_continues_the_same_game() {
+    let mut simulation = Simulation::new(11);
+    play(&mut simulation, 0..200);
+    assert_eq!(simulation.state(), AppState::InGame);
+
+    let path = std::env::temp_dir().join("bevy-shoot-em-up-snapshot-test.json");
+    simulation.snapshot().save(&path).unwrap();
+    let snapshot = Snapshot::load(&path).unwrap();
+
+    // A different seed, so that only the snapshot can make the games match
+    let mut restored = Simulation::new(12);
+    restored.restore(&snapshot);
+    assert_eq!(restored.enemy_count(), simulation.enemy_count());
+    assert_eq!(restored.bullet_count(), simulation.bullet_count());
+    assert_eq!(
+        restored.enemy_bullet_transforms(),
+        simulation.enemy_bullet_transforms()
+    );
+    assert_eq!(
+        cover_hit_points(&mut restored),
+        cover_hit_points(&mut simulation)
+    );
+    assert_eq!(restored.run_stats(), simulation.run_stats());
+
+    play(&mut simulation, 200..500);
+    play(&mut restored, 200..500);
+    assert_eq!(restored.state(), simulation.state());
+    assert_eq!(
+        restored.destroyed_enemy_count(),
+        simulation.destroyed_enemy_count()
+    );
+    assert_eq!(restored.score(), simulation.score());
+    assert_eq!(restored.player_transform(), simulation.player_transform());
+    assert_eq!(restored.enemy_transforms(), simulation.enemy_transforms());
+    assert_eq!(
+        restored.enemy_bullet_transforms(),
+        simulation.enemy_bullet_transforms()
+    );
+    assert_eq!(restored.run_stats(), simulation.run_stats());
+}
+
+#[test]
+fn enemy_health_is_restored_as_captured() {
+    let mut simulation = Simulation::new(11);
+    simulation.spawn_enemy(Vec2::new(0., 200.));
+    let mut snapshot = simulation.snapshot();
+    let enemy = &mut snapshot.enemies[0];
+    enemy.hit_points = 2;
+    enemy.max_hit_points = enemy.kind.hit_points + 3;
+    let max = enemy.max_hit_points;
+
+    let mut restored = Simulation::new(12);
+    restored.restore(&snapshot);
+    let world = restored.world_mut();
+    let health: Vec<Health> = world.query::<&Health>().iter(world).copied().collect();
+    assert_eq!(health, [Health { current: 2, max }]);
+}
+
+#[test]
+fn snapshot_from_other_version_is_rejected() {
+    let path = std::env::temp_dir().join("bevy-shoot-em-up-old-snapshot-test.json");
+    std::fs::write(&path, format!(r#"{{"version": {}}}"#, SNAPSHOT_VERSION + 1)).unwrap();
+    assert!(matches!(
+        Snapshot::load(&path),
+        Err(SnapshotError::Version(_))
+    ));
+}
+
+#[test]
+fn snapshot_missing_random_streams_is_rejected() {
+    let mut simulation = Simulation::new(7);
+    simulation.advance(10);
+    let mut snapshot = serde_json::to_value(simulation.snapshot()).unwrap();
+    snapshot["rng"]["streams"].as_array_mut().unwrap().pop();
+
+    let path = std::env::temp_dir().join("bevy-shoot-em-up-short-rng-snapshot-test.json");
+    std::fs::write(&path, snapshot.to_string()).unwrap();
+    assert!(matches!(
+        Snapshot::load(&path),
+        Err(SnapshotError::Format(_))
+    ));
+}
@@ -0,0 +1,183 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    Direction, EnemyKilledEvent, EnemyKind, EnemySpawnSide, EnemySpawnedEvent, EnemySplit,
+    EnemyTable, EnemyTableError, PlayerInput, Simulation, SpawnFrom, SpawnGroup, Wave, WaveScript,
+    MAX_NUMBER_OF_ENEMIES,
+};
+
+fn splitter() -> EnemyKind {
+    EnemyKind {
+        name: "splitter".into(),
+        size: 30.,
+        score: 2,
+        split: Some(EnemySplit {
+            children: 2,
+            shrink: 0.5,
+            speedup: 1.5,
+            min_size: 7.,
+        }),
+        ..default()
+    }
+}
+
+#[test]
+fn children_are_smaller_and_faster_down_to_the_minimum_size() {
+    let parent = EnemyKind {
+        hit_points: 3,
+        ..splitter()
+    };
+    let child = parent.child().unwrap();
+    assert_eq!((child.size, child.speed, child.hit_points), (15., 1.5, 2));
+    assert_eq!(child.score, parent.score);
+    let grandchild = child.child().unwrap();
+    assert_eq!((grandchild.size, grandchild.speed), (7.5, 2.25));
+    assert!(grandchild.child().is_none());
+    assert!(EnemyTable::default()
+        .kinds
+        .iter()
+        .any(|kind| kind.child().is_some()));
+}
+
+#[test]
+fn invalid_splits_are_rejected() {
+    let ron = |kind: EnemyKind| ron::to_string(&EnemyTable { kinds: vec![kind] }).unwrap();
+    assert!(EnemyTable::from_ron(&ron(splitter())).is_ok());
+    for split in [
+        EnemySplit {
+            children: 1,
+            ..splitter().split.unwrap()
+        },
+        EnemySplit {
+            shrink: 1.,
+            ..splitter().split.unwrap()
+        },
+        EnemySplit {
+            speedup: 0.5,
+            ..splitter().split.unwrap()
+        },
+    ] {
+        let kind = EnemyKind {
+            split: Some(split),
+            ..splitter()
+        };
+        assert!(matches!(
+            EnemyTable::from_ron(&ron(kind)),
+            Err(EnemyTableError::Invalid(_))
+        ));
+    }
+}
+
+#[test]
+fn killed_splitters_break_apart() {
+    let mut simulation = Simulation::with_app(1, |app| {
+        // No other enemies in the way
+        let mut script = WaveScript::default();
+        script.waves[0].rest = 1000.;
+        app.insert_resource(script);
+    });
+    let player = simulation.player_transform().unwrap().translation;
+    simulation.spawn_enemy_of_kind(Vec2::new(player.x, player.y + 150.), &splitter());
+    // A single shot, so that no other bullet hits the children
+    simulation.set_input(PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    });
+    simulation.tick();
+    simulation.set_input(PlayerInput::default());
+
+    let mut killed = Vec::new();
+    let mut children = Vec::new();
+    simulation.advance_until(60, |simulation| {
+        killed.extend(simulation.events::<EnemyKilledEvent>());
+        children.extend(simulation.events::<EnemySpawnedEvent>());
+        !killed.is_empty()
+    });
+    assert_eq!(killed.len(), 1);
+    assert_eq!(simulation.score(), 2);
+    assert_eq!(simulation.destroyed_enemy_count(), 1);
+    assert_eq!(children.len(), 2);
+    assert!(children
+        .iter()
+        .all(|child| child.side == EnemySpawnSide::Split && child.position == killed[0].position));
+
+    // They fly apart, across the path of the bullet, and count as enemies of
+    // their own
+    simulation.advance(10);
+    assert_eq!(simulation.enemy_count(), 2);
+    let x = killed[0].position.x;
+    let mut xs: Vec<f32> = children
+        .iter()
+        .map(|child| {
+            let transform = simulation.world().get::<Transform>(child.enemy).unwrap();
+            assert_eq!(transform.scale.x, 15.);
+            transform.translation.x
+        })
+        .collect();
+    xs.sort_by(f32::total_cmp);
+    assert!(xs[0] < x - 5. && xs[1] > x + 5.);
+}
+
+#[test]
+fn splitters_killed_at_the_cap_hold_back_the_wave() {
+    let player = Simulation::new(1).player_transform().unwrap().translation;
+    // Posts keep coming in, out of the bullet's way, as long as there is room
+    let post = EnemyKind {
+        name: "post".into(),
+        speed: 0.,
+        ..default()
+    };
+    let mut simulation = Simulation::with_app(1, |app| {
+        app.insert_resource(EnemyTable {
+            kinds: vec![post.clone()],
+        })
+        .insert_resource(WaveScript {
+            waves: vec![Wave {
+                groups: vec![SpawnGroup {
+                    kind: Some("post".into()),
+                    count: MAX_NUMBER_OF_ENEMIES as u32 + 2,
+                    from: SpawnFrom::Point(player.x + 100., player.y + 250.),
+                    delay: 0.,
+                    interval: 0.1,
+                }],
+                ..default()
+            }],
+            ..WaveScript::default()
+        });
+    });
+    simulation.tick();
+    simulation.spawn_enemy_of_kind(
+        Vec2::new(player.x, player.y + 150.),
+        &EnemyKind {
+            speed: 0.,
+            ..splitter()
+        },
+    );
+    assert!(simulation.advance_until(120, |simulation| {
+        simulation.enemy_count() == MAX_NUMBER_OF_ENEMIES
+    }));
+
+    simulation.set_input(PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    });
+    simulation.tick();
+    simulation.set_input(PlayerInput::default());
+
+    let mut killed = Vec::new();
+    let mut spawned = Vec::new();
+    simulation.advance_until(60, |simulation| {
+        killed.extend(simulation.events::<EnemyKilledEvent>());
+        spawned.extend(simulation.events::<EnemySpawnedEvent>());
+        !killed.is_empty()
+    });
+    assert_eq!(killed.len(), 1);
+    // Every child comes out, past the cap, and no post comes in until there is
+    // room again
+    simulation.advance(60);
+    spawned.extend(simulation.events::<EnemySpawnedEvent>());
+    assert_eq!(spawned.len(), 2);
+    assert!(spawned
+        .iter()
+        .all(|spawn| spawn.side == EnemySpawnSide::Split));
+    assert_eq!(simulation.enemy_count(), MAX_NUMBER_OF_ENEMIES + 1);
+}
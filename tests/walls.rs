@@ -0,0 +1,215 @@
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    AppState, ArenaBounds, ArenaEdge, ArenaLayout, BulletRicochet, Cover, CoverDestroyedEvent,
+    Direction, Obstacle, PlayerInput, Simulation, ENEMY_SIZE, PILLAR_SIZE, PLAYER_SIZE,
+};
+
+fn fire_once(simulation: &mut Simulation) {
+    simulation.set_input(PlayerInput {
+        fire: Direction { x: 0., y: 1. },
+        ..default()
+    });
+    simulation.tick();
+    simulation.set_input(PlayerInput::default());
+}
+
+// Heights of the single bullet in the arena, tick by tick until it is gone
+fn bullet_heights(simulation: &mut Simulation) -> Vec<f32> {
+    let mut heights = Vec::new();
+    simulation.advance_until(
+        1000,
+        |simulation| match simulation.bullet_transforms()[..] {
+            [bullet] => {
+                heights.push(bullet.translation.y);
+                false
+            }
+            _ => true,
+        },
+    );
+    heights
+}
+
+#[test]
+fn bullets_are_destroyed_by_walls() {
+    let mut simulation = Simulation::new(1);
+    fire_once(&mut simulation);
+    let heights = bullet_heights(&mut simulation);
+
+    assert!(heights.windows(2).all(|pair| pair[1] >= pair[0]));
+    let top = simulation.world().resource::<ArenaBounds>().max.y;
+    assert!(*heights.last().unwrap() <= top);
+}
+
+#[test]
+fn ricocheting_bullets_bounce_off_walls() {
+    let mut simulation = Simulation::with_app(1, |app| {
+        app.insert_resource(BulletRicochet { bounces: 1 });
+    });
+    fire_once(&mut simulation);
+    let heights = bullet_heights(&mut simulation);
+
+    // Up to the top wall, then back down to the bottom one
+    let highest = heights
+        .iter()
+        .position(|&height| height == heights.iter().copied().fold(f32::MIN, f32::max))
+        .unwrap();
+    assert!(heights[..=highest]
+        .windows(2)
+        .all(|pair| pair[1] >= pair[0]));
+    assert!(heights[highest..].windows(2).all(|pair| pair[1] <= pair[0]));
+    let bounds = simulation.world().resource::<ArenaBounds>().clone();
+    assert!(*heights.last().unwrap() < bounds.min.y + ENEMY_SIZE);
+}
+
+#[test]
+fn enemies_are_kept_inside_the_walls() {
+    let mut simulation = Simulation::new(1);
+    let bounds = simulation.world().resource::<ArenaBounds>().clone();
+    // Pushed into the wall by the enemies around it
+    for offset in [-30., -15., 15., 30.] {
+        simulation.spawn_enemy(Vec2::new(bounds.min.x + 15., 100. + offset));
+    }
+    simulation.spawn_enemy(Vec2::new(bounds.min.x + 15., 100.));
+    simulation.advance(30);
+
+    for enemy in simulation.enemy_transforms() {
+        assert!(enemy.translation.x - ENEMY_SIZE / 2. >= bounds.min.x - 1e-3);
+    }
+}
+
+#[test]
+fn arena_bounds_follow_the_walls() {
+    let mut simulation = Simulation::new(1);
+    let bounds = simulation.world().resource::<ArenaBounds>().clone();
+    let world = simulation.world_mut();
+    let mut walls = world.query::<(&ArenaEdge, &mut Transform)>();
+    for (edge, mut transform) in walls.iter_mut(world) {
+        if *edge == ArenaEdge::Top {
+            transform.translation.y -= 100.;
+        }
+    }
+    simulation.tick();
+
+    let moved = simulation.world().resource::<ArenaBounds>().clone();
+    assert_eq!(moved.max.y, bounds.max.y - 100.);
+    assert_eq!(moved.min, bounds.min);
+    assert_eq!(moved.max.x, bounds.max.x);
+}
+
+fn arena_with(obstacle: Obstacle) -> Simulation {
+    Simulation::with_app(1, |app| {
+        app.insert_resource(ArenaLayout {
+            obstacles: vec![obstacle],
+        });
+    })
+}
+
+#[test]
+fn obstacles_stop_bullets() {
+    let mut simulation = arena_with(Obstacle::Block {
+        center: Vec2::new(0., 0.),
+        size: Vec2::new(100., 20.),
+    });
+    fire_once(&mut simulation);
+    let heights = bullet_heights(&mut simulation);
+
+    assert!(*heights.last().unwrap() <= -10.);
+}
+
+#[test]
+fn obstacles_block_the_player() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    let mut simulation = arena_with(Obstacle::Pillar {
+        center: Vec2::new(player.x + 100., player.y),
+    });
+
+    simulation.set_input(PlayerInput {
+        movement: Direction { x: 1., y: 0. },
+        ..default()
+    });
+    simulation.advance(30);
+    let x = simulation.player_transform().unwrap().translation.x;
+    assert!(x + PLAYER_SIZE / 2. <= player.x + 100. - PILLAR_SIZE / 2. + 1e-3);
+}
+
+#[test]
+fn enemies_find_their_way_around_obstacles() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    // A wide block right between the enemy and the player
+    let mut simulation = arena_with(Obstacle::Block {
+        center: Vec2::new(player.x, player.y + 100.),
+        size: Vec2::new(300., 20.),
+    });
+    let enemy = simulation.spawn_enemy(Vec2::new(player.x, player.y + 200.));
+
+    assert!(simulation.advance_until(600, |simulation| {
+        let position = simulation
+            .world()
+            .get::<Transform>(enemy)
+            .unwrap()
+            .translation;
+        position.y < player.y + 100.
+    }));
+}
+
+#[test]
+fn cover_absorbs_bullets_until_it_breaks() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    let mut simulation = arena_with(Obstacle::Cover {
+        center: Vec2::new(player.x, player.y + 100.),
+        size: Vec2::new(60., 14.),
+        hit_points: 3,
+    });
+    let world = simulation.world_mut();
+    let cover = world.query_filtered::<Entity, With<Cover>>().single(world);
+
+    for hit_points in [2, 1] {
+        fire_once(&mut simulation);
+        let heights = bullet_heights(&mut simulation);
+        assert!(*heights.last().unwrap() < player.y + 100.);
+        let cover = simulation.world().get::<Cover>(cover).unwrap();
+        assert_eq!(cover.hit_points, hit_points);
+    }
+
+    fire_once(&mut simulation);
+    let mut destroyed = Vec::new();
+    simulation.advance_until(60, |simulation| {
+        destroyed.extend(simulation.events::<CoverDestroyedEvent>());
+        simulation.bullet_count() == 0
+    });
+    assert_eq!(destroyed.len(), 1);
+    assert_eq!(destroyed[0].cover, cover);
+    assert!(simulation.world().get_entity(cover).is_none());
+
+    // Nothing left to stop the next bullet before the top wall
+    fire_once(&mut simulation);
+    let heights = bullet_heights(&mut simulation);
+    assert!(*heights.last().unwrap() > player.y + 300.);
+}
+
+#[test]
+fn broken_cover_is_put_back_up_for_the_next_game() {
+    let mut simulation = Simulation::new(1);
+    let player = simulation.player_transform().unwrap().translation;
+    let mut simulation = arena_with(Obstacle::Cover {
+        center: Vec2::new(player.x, player.y + 100.),
+        size: Vec2::new(60., 14.),
+        hit_points: 1,
+    });
+    fire_once(&mut simulation);
+    assert!(simulation.advance_until(60, |simulation| !simulation
+        .events::<CoverDestroyedEvent>()
+        .is_empty()));
+    let world = simulation.world_mut();
+    assert_eq!(world.query::<&Cover>().iter(world).count(), 0);
+
+    simulation.spawn_enemy(Vec2::new(player.x, player.y));
+    assert!(simulation.advance_until(10, |simulation| simulation.state() == AppState::GameOver));
+    assert!(simulation.advance_until(200, |simulation| simulation.state() == AppState::InGame));
+    let world = simulation.world_mut();
+    let covers: Vec<Cover> = world.query::<&Cover>().iter(world).copied().collect();
+    assert_eq!(covers, [Cover::new(1)]);
+}
@@ -0,0 +1,158 @@
+mod common;
+
+use bevy::prelude::*;
+use bevy_shoot_em_up::{
+    EnemySpawnSide, EnemySpawnedEvent, Simulation, SpawnFrom, SpawnGroup, Wave, WaveDirector,
+    WaveScript, WaveScriptError, WaveStartedEvent,
+};
+use common::script;
+
+fn group(count: u32, from: SpawnFrom) -> SpawnGroup {
+    SpawnGroup {
+        kind: Some("grunt".into()),
+        count,
+        from,
+        delay: 0.,
+        interval: 0.5,
+    }
+}
+
+#[test]
+fn default_script_is_the_asset_file() {
+    let ron = std::fs::read_to_string("assets/default.waves.ron").unwrap();
+    assert_eq!(WaveScript::from_ron(&ron).unwrap(), WaveScript::default());
+    assert!(!WaveScript::default().waves.is_empty());
+}
+
+#[test]
+fn invalid_scripts_are_rejected() {
+    assert!(matches!(
+        WaveScript::from_ron("(waves: [])"),
+        Err(WaveScriptError::Format(_))
+    ));
+
+    let ron = |script: &WaveScript| ron::to_string(script).unwrap();
+    let mut script = script(vec![Wave {
+        rest: 1.,
+        groups: vec![group(0, SpawnFrom::Top)],
+        ..default()
+    }]);
+    assert!(matches!(
+        WaveScript::from_ron(&ron(&script)),
+        Err(WaveScriptError::Invalid(_))
+    ));
+
+    script.waves[0].groups[0].count = 3;
+    assert_eq!(WaveScript::from_ron(&ron(&script)).unwrap(), script);
+
+    script.endless.speedup = 2.;
+    assert!(matches!(
+        WaveScript::from_ron(&ron(&script)),
+        Err(WaveScriptError::Invalid(_))
+    ));
+}
+
+#[test]
+fn spawns_come_in_order() {
+    let script = script(vec![Wave {
+        rest: 0.,
+        groups: vec![
+            group(2, SpawnFrom::Top),
+            SpawnGroup {
+                delay: 0.25,
+                ..group(2, SpawnFrom::Left)
+            },
+        ],
+        ..default()
+    }]);
+    let times: Vec<(f32, SpawnFrom)> = script
+        .spawns(1)
+        .iter()
+        .map(|spawn| (spawn.time, spawn.from))
+        .collect();
+    assert_eq!(
+        times,
+        [
+            (0., SpawnFrom::Top),
+            (0.25, SpawnFrom::Left),
+            (0.5, SpawnFrom::Top),
+            (0.75, SpawnFrom::Left),
+        ]
+    );
+}
+
+#[test]
+fn endless_waves_grow_after_the_script() {
+    let script = script(vec![Wave {
+        rest: 0.,
+        groups: vec![group(1, SpawnFrom::Top)],
+        ..default()
+    }]);
+    assert_eq!(script.rest(2), 1.);
+    let first = script.spawns(2);
+    let second = script.spawns(3);
+    assert_eq!(first.len(), 4);
+    assert_eq!(second.len(), 6);
+    assert!(first.iter().all(|spawn| spawn.kind.is_none()));
+    assert_eq!(first[1].time, 1.);
+    assert_eq!(second[1].time, 0.5);
+    assert_eq!(script.spawns(10)[1].time, 0.2);
+}
+
+#[test]
+fn wave_zero_is_neither_scripted_nor_a_boss_wave() {
+    let mut script = script(vec![Wave {
+        rest: 3.,
+        groups: vec![group(1, SpawnFrom::Top)],
+        ..default()
+    }]);
+    script.endless.boss_every = 1;
+    assert_eq!(script.rest(0), 1.);
+    assert_eq!(script.spawns(0).len(), 4);
+    assert!(script.boss(0).is_none());
+
+    // Boss waves without any bosses to fight
+    assert!(script.boss(2).is_none());
+    script.waves.clear();
+    assert!(script.boss(0).is_none());
+    assert!(script.boss(1).is_none());
+}
+
+#[test]
+fn waves_wait_for_the_arena_to_be_cleared() {
+    let point = Vec2::new(0., 150.);
+    let mut simulation = Simulation::with_app(1, |app| {
+        app.insert_resource(script(vec![Wave {
+            rest: 0.,
+            groups: vec![group(2, SpawnFrom::Point(point.x, point.y))],
+            ..default()
+        }]));
+    });
+
+    let mut started = Vec::new();
+    let mut spawned = Vec::new();
+    simulation.advance_until(60, |simulation| {
+        started.extend(simulation.events::<WaveStartedEvent>());
+        spawned.extend(simulation.events::<EnemySpawnedEvent>());
+        spawned.len() == 2
+    });
+    assert_eq!(started.len(), 1);
+    assert_eq!(started[0].wave, 1);
+    assert_eq!(spawned.len(), 2);
+    assert!(spawned
+        .iter()
+        .all(|spawn| spawn.side == EnemySpawnSide::Point && spawn.position == point));
+
+    // Nothing more comes in while the wave's enemies are around
+    simulation.advance(10);
+    assert_eq!(simulation.world().resource::<WaveDirector>().wave, 1);
+    assert!(simulation.events::<EnemySpawnedEvent>().is_empty());
+
+    for spawn in &spawned {
+        simulation.world_mut().despawn(spawn.enemy);
+    }
+    simulation.tick();
+    let director = simulation.world().resource::<WaveDirector>();
+    assert_eq!(director.wave, 2);
+    assert!(director.is_between_waves());
+}